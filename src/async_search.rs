@@ -0,0 +1,341 @@
+//! Async page fetching, available with the `async` feature.
+//!
+//! Unlike the blocking API, this builds for `wasm32-unknown-unknown`, where
+//! requests are made with the browser's `fetch`. Browsers enforce CORS on
+//! these requests: unless the API sends the appropriate
+//! `Access-Control-Allow-Origin` headers, fetches from web pages will fail
+//! with an opaque [`Error::Request`](Error::Request). Privileged contexts
+//! (such as browser extensions with host permissions, or Tauri apps) aren't
+//! subject to this restriction.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use reqwest::header::{ACCEPT, RETRY_AFTER};
+use reqwest::StatusCode;
+
+use crate::transport::parse_retry_after;
+use crate::{
+    default_base_url, page_url, parse_page, Error, QueryEncoding, SearchResult, DEFAULT_ACCEPT,
+    DEFAULT_MAX_RESPONSE_BYTES, PAGE_SIZE,
+};
+
+/// Fetch a single page of SunXDCC results for `query`, using the given async
+/// HTTP client.
+///
+/// Pages are numbered from zero; a page with no results marks the end of the
+/// search. Results are returned in the order that the API returns them.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), sunxdcc::Error> {
+/// let client = reqwest::Client::new();
+/// for result in sunxdcc::search_page(&client, "the hitchhiker's guide", 0).await? {
+///     println!("{:?}", result);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip(client), fields(result_count))
+)]
+pub async fn search_page(
+    client: &reqwest::Client,
+    query: &str,
+    page: usize,
+) -> Result<Vec<SearchResult>, Error> {
+    let url = page_url(&default_base_url(), query, page, QueryEncoding::default());
+    let response = client
+        .get(url)
+        .header(ACCEPT, DEFAULT_ACCEPT)
+        .send()
+        .await?;
+
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+
+        return Err(Error::RateLimited {
+            retry_after: retry_after,
+        });
+    }
+
+    // Fail early when the server tells us the body is too large, rather than
+    // buffering it first.
+    let limit = DEFAULT_MAX_RESPONSE_BYTES;
+    if let Some(len) = response.content_length().filter(|len| *len > limit as u64) {
+        return Err(Error::ResponseTooLarge {
+            limit: limit,
+            got_at_least: usize::try_from(len).unwrap_or(usize::MAX),
+        });
+    }
+
+    let body = read_body(response, limit).await?;
+    let results = parse_page(&body)?;
+
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("result_count", results.len());
+
+    Ok(results)
+}
+
+/// Read `response`'s body incrementally, failing as soon as it exceeds `limit`
+/// bytes.
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_body(mut response: reqwest::Response, limit: usize) -> Result<Vec<u8>, Error> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        append_chunk(&mut body, &chunk, limit)?;
+    }
+
+    Ok(body)
+}
+
+/// Read `response`'s body incrementally, failing as soon as it exceeds `limit`
+/// bytes.
+///
+/// On wasm, responses can only be read incrementally as a stream.
+#[cfg(target_arch = "wasm32")]
+async fn read_body(response: reqwest::Response, limit: usize) -> Result<Vec<u8>, Error> {
+    let mut chunks = std::pin::pin!(response.bytes_stream());
+    let mut body = Vec::new();
+    while let Some(chunk) = std::future::poll_fn(|cx| chunks.as_mut().poll_next(cx)).await {
+        append_chunk(&mut body, &chunk?, limit)?;
+    }
+
+    Ok(body)
+}
+
+/// Append `chunk` to `body`, failing if that takes it past `limit` bytes.
+fn append_chunk(body: &mut Vec<u8>, chunk: &[u8], limit: usize) -> Result<(), Error> {
+    body.extend_from_slice(chunk);
+    if body.len() > limit {
+        return Err(Error::ResponseTooLarge {
+            limit: limit,
+            got_at_least: body.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// A page fetch in progress.
+///
+/// Futures from the browser's `fetch` aren't `Send`, so neither is this on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+type PageFuture = Pin<Box<dyn Future<Output = Result<Vec<SearchResult>, Error>> + Send>>;
+#[cfg(target_arch = "wasm32")]
+type PageFuture = Pin<Box<dyn Future<Output = Result<Vec<SearchResult>, Error>>>>;
+
+/// A future that completes once a rate limiter permits another request.
+///
+/// See [`AsyncSearchResults::with_rate_limiter`]. As with page fetches, this
+/// must be `Send` except on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+pub type LimiterFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+/// A future that completes once a rate limiter permits another request.
+///
+/// See [`AsyncSearchResults::with_rate_limiter`]. As with page fetches, this
+/// must be `Send` except on wasm.
+#[cfg(target_arch = "wasm32")]
+pub type LimiterFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+/// A rate limiter, as a function producing a future to await before each
+/// request.
+type RateLimiter = Arc<dyn Fn() -> LimiterFuture + Send + Sync>;
+
+/// A [`Stream`](futures_core::Stream) of search results, fetched with an async
+/// HTTP client.
+///
+/// This is the async counterpart to `SearchResults`: pages are fetched lazily
+/// as the stream is polled, and the stream ends at the first empty page. Use
+/// `StreamExt::next` or `TryStreamExt::try_collect` from the `futures` crate
+/// to consume it.
+///
+/// ```no_run
+/// let results = sunxdcc::search_async("the hitchhiker's guide to the galaxy");
+/// ```
+pub struct AsyncSearchResults {
+    /// The HTTP client to use for all requests.
+    client: reqwest::Client,
+    /// The search query.
+    query: String,
+    /// The next page to be fetched.
+    current_page: usize,
+    /// The current list of results, in reverse order for popping.
+    current_results: Vec<SearchResult>,
+    /// The page fetch in progress, if any.
+    pending: Option<PageFuture>,
+    /// Whether we've observed the end of the results.
+    finished: bool,
+    /// The rate limiter to await before each page fetch, if any.
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl AsyncSearchResults {
+    /// Create a new `AsyncSearchResults` for `query`, fetching pages with the
+    /// given client.
+    pub fn new(client: reqwest::Client, query: impl Into<String>) -> Self {
+        Self {
+            client: client,
+            query: query.into(),
+            current_page: 0,
+            current_results: Vec::with_capacity(PAGE_SIZE),
+            pending: None,
+            finished: false,
+            rate_limiter: None,
+        }
+    }
+
+    /// Await the future produced by `limiter` before fetching each page.
+    ///
+    /// This is a hook for an external rate limiter, such as one from the
+    /// `governor` crate, which can be shared between many concurrent searches
+    /// to keep them within a global request budget:
+    ///
+    /// ```ignore
+    /// let limiter = Arc::new(RateLimiter::direct(Quota::per_second(nonzero!(2u32))));
+    /// let results = sunxdcc::search_async("the hitchhiker's guide")
+    ///     .with_rate_limiter(move || {
+    ///         let limiter = Arc::clone(&limiter);
+    ///         Box::pin(async move { limiter.until_ready().await })
+    ///     });
+    /// ```
+    pub fn with_rate_limiter(
+        mut self,
+        limiter: impl Fn() -> LimiterFuture + Send + Sync + 'static,
+    ) -> Self {
+        self.rate_limiter = Some(Arc::new(limiter));
+        self
+    }
+}
+
+impl fmt::Debug for AsyncSearchResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncSearchResults")
+            .field("query", &self.query)
+            .field("page", &self.current_page)
+            .field("buffered", &self.current_results.len())
+            .field("rate_limited", &self.rate_limiter.is_some())
+            .finish()
+    }
+}
+
+impl Stream for AsyncSearchResults {
+    type Item = Result<SearchResult, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(result) = this.current_results.pop() {
+                return Poll::Ready(Some(Ok(result)));
+            }
+
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            let pending = this.pending.get_or_insert_with(|| {
+                let (client, query, page) =
+                    (this.client.clone(), this.query.clone(), this.current_page);
+                let limiter = this.rate_limiter.clone();
+                Box::pin(async move {
+                    if let Some(limiter) = limiter {
+                        limiter().await;
+                    }
+                    search_page(&client, &query, page).await
+                })
+            });
+
+            let page = ready!(pending.as_mut().poll(cx));
+            this.pending = None;
+
+            #[cfg(feature = "tracing")]
+            match &page {
+                Ok(results) => tracing::debug!(
+                    query = %this.query,
+                    current_page = this.current_page,
+                    result_count = results.len(),
+                    "received page"
+                ),
+                Err(e) => tracing::debug!(
+                    query = %this.query,
+                    current_page = this.current_page,
+                    error = %e,
+                    "failed to fetch page"
+                ),
+            }
+
+            // Like `SearchResults`, a failed page is retried on the next poll.
+            let mut results = page?;
+            if results.is_empty() {
+                this.finished = true;
+                return Poll::Ready(None);
+            }
+
+            results.reverse();
+            this.current_results = results;
+            this.current_page += 1;
+        }
+    }
+}
+
+/// Search SunXDCC for the given `query`, returning a stream of results.
+///
+/// This is the async counterpart to `search`, and uses a default
+/// [`reqwest::Client`].
+pub fn search_async(query: &str) -> AsyncSearchResults {
+    AsyncSearchResults::new(reqwest::Client::new(), query)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+
+    #[test]
+    fn parses_pages() {
+        let body = br##"{"network":["irc.foo.net","irc.bar.net"],"channel":["#foo","#bar"],
+            "bot":["foobot","barbot"],"fsize":["[1M]","[2.5G]"],"fname":["a.txt","b.mkv"],
+            "packnum":["#1","#22"],"gets":["3x","10x"],"botrec":["Na","1000.25kB/s"]}"##;
+
+        let results = parse_page(body).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].filename, "a.txt");
+        assert_eq!(results[0].upload_speed, None);
+        assert_eq!(results[1].filename, "b.mkv");
+        assert_eq!(results[1].packet_number_raw(), 22);
+        assert_eq!(results[1].upload_speed.as_deref(), Some("1000.25kB/s"));
+    }
+
+    #[test]
+    fn rejects_inconsistent_pages() {
+        let body = br#"{"network":["irc.foo.net"],"channel":[],"bot":[],"fsize":[],
+            "fname":[],"packnum":[],"gets":[],"botrec":[]}"#;
+        assert!(matches!(parse_page(body), Err(Error::Malformed(_))));
+        assert!(matches!(parse_page(b"<html>"), Err(Error::Malformed(_))));
+    }
+
+    #[test]
+    fn limits_body_size() {
+        let mut body = vec![];
+        append_chunk(&mut body, &[0; 8], 10).unwrap();
+        assert_eq!(
+            append_chunk(&mut body, &[0; 8], 10),
+            Err(Error::ResponseTooLarge {
+                limit: 10,
+                got_at_least: 16
+            })
+        );
+    }
+}
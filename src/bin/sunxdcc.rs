@@ -0,0 +1,216 @@
+//! A command-line interface to SunXDCC, available with the `cli` feature.
+
+use std::error::Error as _;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{Map, Value};
+use sunxdcc::{Error, SearchBuilder, SearchResult};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// The API endpoint to query, instead of SunXDCC's own.
+    #[arg(long, global = true)]
+    base_url: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search for files matching a query.
+    Search {
+        /// The search query.
+        query: String,
+
+        /// The maximum number of pages to fetch.
+        #[arg(long, value_name = "N")]
+        pages: Option<usize>,
+
+        /// The maximum number of results to print.
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// The output format.
+        #[arg(long, value_enum, default_value_t = Output::Plain)]
+        output: Output,
+
+        /// Only print the given fields, in order.
+        #[arg(long, value_enum, value_delimiter = ',')]
+        fields: Vec<Field>,
+    },
+}
+
+/// The output format for results.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Output {
+    /// A single JSON array of results.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    /// One human-readable line per result.
+    Plain,
+}
+
+/// A selectable result field, named as in the SunXDCC API.
+#[derive(Clone, Copy, ValueEnum)]
+enum Field {
+    Network,
+    Channel,
+    Bot,
+    #[value(name = "fsize", alias = "filesize")]
+    Filesize,
+    #[value(name = "fname", alias = "filename")]
+    Filename,
+    #[value(name = "packnum", alias = "packet-number")]
+    PacketNumber,
+    #[value(name = "gets", alias = "download-count")]
+    DownloadCount,
+    #[value(name = "botrec", alias = "upload-speed")]
+    UploadSpeed,
+}
+
+impl Field {
+    /// Returns this field's key in a serialized `SearchResult`.
+    fn key(self) -> &'static str {
+        match self {
+            Field::Network => "network",
+            Field::Channel => "channel",
+            Field::Bot => "bot",
+            Field::Filesize => "filesize",
+            Field::Filename => "filename",
+            Field::PacketNumber => "packet_number",
+            Field::DownloadCount => "download_count",
+            Field::UploadSpeed => "upload_speed",
+        }
+    }
+}
+
+/// Serialize `result` as a JSON object, keeping only `fields` if any are given.
+fn to_json(result: &SearchResult, fields: &[Field]) -> Value {
+    let Value::Object(mut all) = serde_json::to_value(result).unwrap_or_default() else {
+        return Value::Null;
+    };
+
+    if fields.is_empty() {
+        return Value::Object(all);
+    }
+
+    let selected: Map<String, Value> = fields
+        .iter()
+        .filter_map(|field| Some((field.key().into(), all.remove(field.key())?)))
+        .collect();
+    Value::Object(selected)
+}
+
+/// Format `result` for plain output, as tab-separated `fields` if any are given.
+fn to_plain(result: &SearchResult, fields: &[Field]) -> String {
+    if fields.is_empty() {
+        return result.to_string();
+    }
+
+    fields
+        .iter()
+        .map(|field| match field {
+            Field::Network => result.network.to_string(),
+            Field::Channel => result.channel.to_string(),
+            Field::Bot => result.bot.to_string(),
+            Field::Filesize => result.filesize.clone(),
+            Field::Filename => result.filename.clone(),
+            Field::PacketNumber => result.packet_number.clone(),
+            Field::DownloadCount => result.download_count.clone(),
+            Field::UploadSpeed => result.upload_speed.clone().unwrap_or_default(),
+        })
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+fn search(
+    builder: SearchBuilder,
+    query: &str,
+    pages: Option<usize>,
+    limit: Option<usize>,
+    output: Output,
+    fields: &[Field],
+) -> Result<(), Error> {
+    let builder = match pages {
+        Some(pages) => builder.max_pages(pages),
+        None => builder,
+    };
+    let client = builder.build()?;
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut printed = 0;
+    let mut array = vec![];
+
+    let result = (|| {
+        let mut pages = client.search(query).page_iter();
+
+        // Check the limit before each page, so that we don't fetch a page
+        // only to discard it.
+        while printed < limit {
+            let Some(page) = pages.next() else {
+                break;
+            };
+
+            for result in page?.into_iter().take(limit - printed) {
+                match output {
+                    Output::Json => array.push(to_json(&result, fields)),
+                    Output::Ndjson => println!("{}", to_json(&result, fields)),
+                    Output::Plain => println!("{}", to_plain(&result, fields)),
+                }
+                printed += 1;
+            }
+        }
+
+        Ok(())
+    })();
+
+    // Emit whatever we collected even on error, so that stdout always remains
+    // parseable; the error itself goes to stderr.
+    if output == Output::Json {
+        println!("{}", Value::Array(array));
+    }
+
+    result
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let mut builder = SearchBuilder::new();
+    if let Some(base_url) = &cli.base_url {
+        builder = builder.base_url(base_url);
+    }
+
+    let result = match cli.command {
+        Command::Search {
+            query,
+            pages,
+            limit,
+            output,
+            fields,
+        } => search(builder, &query, pages, limit, output, &fields),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // Some underlying errors already include their causes in their own
+            // messages, so only append causes that we haven't seen yet.
+            let mut message = e.to_string();
+            let mut source = e.source();
+            while let Some(cause) = source {
+                let cause_message = cause.to_string();
+                if !message.contains(&cause_message) {
+                    message = format!("{message}: {cause_message}");
+                }
+                source = cause.source();
+            }
+            eprintln!("error: {message}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
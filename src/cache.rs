@@ -0,0 +1,156 @@
+//! An in-memory cache of fetched pages.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use url::Url;
+
+/// Configuration for a [`PageCache`](PageCache).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// How long a cached page remains valid.
+    pub ttl: Duration,
+    /// The maximum number of pages to cache.
+    pub capacity: usize,
+}
+
+impl CacheConfig {
+    /// Create a configuration that caches up to `capacity` pages, each for up
+    /// to `ttl`.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use sunxdcc::{CacheConfig, SearchClient};
+    /// let client = SearchClient::builder()
+    ///     .cache(CacheConfig::new(64, Duration::from_secs(30)))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(client.cache().is_some());
+    /// ```
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            ttl: ttl,
+            capacity: capacity,
+        }
+    }
+}
+
+/// A cached page body.
+#[derive(Debug)]
+struct Entry {
+    /// When the page was fetched.
+    fetched_at: Instant,
+    /// When the page was last fetched or served from the cache.
+    last_used: Instant,
+    /// The page's raw body.
+    body: Vec<u8>,
+}
+
+/// An in-memory cache of raw page bodies, keyed by request URL (and therefore
+/// by query and page).
+///
+/// Pages expire once they're older than the configured TTL. When the cache is
+/// full, expired pages are evicted first, followed by the least recently used
+/// page.
+///
+/// A cache is shared by every search made through the same
+/// [`SearchClient`](crate::SearchClient); see
+/// [`SearchBuilder::cache`](crate::SearchBuilder::cache).
+#[derive(Debug)]
+pub struct PageCache {
+    /// The cache's configuration.
+    config: CacheConfig,
+    /// The cached pages.
+    entries: Mutex<HashMap<String, Entry>>,
+    /// The number of lookups that were served from the cache.
+    hits: AtomicU64,
+    /// The number of lookups that weren't.
+    misses: AtomicU64,
+}
+
+impl PageCache {
+    /// Create a new, empty `PageCache`.
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config: config,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<String, Entry>> {
+        // A poisoned lock can only mean that another thread panicked mid-update,
+        // which at worst leaves a stale entry behind.
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the number of lookups that were served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of lookups that were not served from the cache.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Remove every page from the cache.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    /// Look up the body for the given URL, if cached and still valid.
+    pub(crate) fn get(&self, url: &Url) -> Option<Vec<u8>> {
+        let mut entries = self.entries();
+        let body = entries
+            .get_mut(url.as_str())
+            .filter(|entry| entry.fetched_at.elapsed() < self.config.ttl)
+            .map(|entry| {
+                entry.last_used = Instant::now();
+                entry.body.clone()
+            });
+
+        match body {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        body
+    }
+
+    /// Cache the body for the given URL, evicting expired pages (or, failing
+    /// that, the least recently used page) if the cache is full.
+    pub(crate) fn insert(&self, url: &Url, body: Vec<u8>) {
+        if self.config.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries();
+        if entries.len() >= self.config.capacity && !entries.contains_key(url.as_str()) {
+            entries.retain(|_, entry| entry.fetched_at.elapsed() < self.config.ttl);
+
+            if entries.len() >= self.config.capacity {
+                let least_recent = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(least_recent) = least_recent {
+                    entries.remove(&least_recent);
+                }
+            }
+        }
+
+        let now = Instant::now();
+        entries.insert(
+            url.as_str().into(),
+            Entry {
+                fetched_at: now,
+                last_used: now,
+                body: body,
+            },
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Collecting entire searches, with control over how errors are handled.
+
+use crate::{search, Error, SearchResult, SearchResults, DEFAULT_MAX_PAGES};
+
+/// What to do when a page of results fails to fetch while collecting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop at the first error, discarding any results gathered so far.
+    #[default]
+    FailFast,
+    /// Stop at the first error, keeping the results gathered so far in the
+    /// [`CollectError`].
+    KeepPartial,
+    /// Skip any pages that fail to fetch, and carry on with the next one.
+    ///
+    /// Skipped pages count towards the search's page limit (see
+    /// [`CollectOptions::max_pages`]); without one, collection ends after
+    /// skipping 100 pages. Errors that affect the entire search, such as an
+    /// invalid query, still stop collection.
+    SkipFailedPages,
+}
+
+/// Options for [`collect_all`](crate::collect_all).
+#[derive(Clone, Debug, Default)]
+pub struct CollectOptions {
+    /// The maximum number of results to collect, if limited.
+    max_results: Option<usize>,
+    /// The maximum number of pages to fetch, if limited.
+    max_pages: Option<usize>,
+    /// What to do when a page fails to fetch.
+    error_policy: ErrorPolicy,
+}
+
+impl CollectOptions {
+    /// Create options that collect every result, failing on the first error.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop collecting after `max` results.
+    pub fn max_results(mut self, max: usize) -> Self {
+        self.max_results = Some(max);
+        self
+    }
+
+    /// Stop collecting after `max` pages have been fetched successfully, or
+    /// skipped with [`ErrorPolicy::SkipFailedPages`].
+    ///
+    /// This can only lower a page limit set on the search itself. The search's
+    /// default page limit doesn't apply when collecting; see
+    /// [`SearchBuilder::max_pages`](crate::SearchBuilder::max_pages).
+    pub fn max_pages(mut self, max: usize) -> Self {
+        self.max_pages = Some(max);
+        self
+    }
+
+    /// Set what to do when a page fails to fetch.
+    ///
+    /// The default is [`ErrorPolicy::FailFast`].
+    pub fn error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.error_policy = policy;
+        self
+    }
+}
+
+/// An error that stopped a search from being collected, along with any
+/// results gathered before it.
+#[derive(Debug, thiserror::Error)]
+#[error("search failed after collecting {} results", .partial.len())]
+pub struct CollectError {
+    /// The results gathered before the error, if kept.
+    partial: Vec<SearchResult>,
+    /// The error that stopped collection.
+    #[source]
+    source: Error,
+}
+
+impl CollectError {
+    /// Returns the results gathered before the error.
+    ///
+    /// This is always empty with [`ErrorPolicy::FailFast`].
+    pub fn partial(&self) -> &[SearchResult] {
+        &self.partial
+    }
+
+    /// Returns the error that stopped collection.
+    pub fn error(&self) -> &Error {
+        &self.source
+    }
+
+    /// Consume this error, returning the results gathered before it and the
+    /// underlying error.
+    pub fn into_parts(self) -> (Vec<SearchResult>, Error) {
+        (self.partial, self.source)
+    }
+}
+
+impl SearchResults {
+    /// Consume this `SearchResults`, collecting its results according to
+    /// `options`.
+    ///
+    /// Unlike collecting into a `Result<Vec<_>, _>`, this can keep the results
+    /// gathered before an error, or skip failed pages entirely; see
+    /// [`ErrorPolicy`]. Unless a page limit is set, every page is collected.
+    pub fn collect_all(self, options: CollectOptions) -> Result<Vec<SearchResult>, CollectError> {
+        let limited = self.max_pages.is_some() || options.max_pages.is_some();
+        let mut results = self.without_default_page_limit();
+        if let Some(max_pages) = options.max_pages {
+            results.max_pages = results.max_pages.map(|max| max.min(max_pages));
+        }
+
+        let max_results = options.max_results.unwrap_or(usize::MAX);
+        let mut collected = vec![];
+        let mut skipped = 0;
+
+        while collected.len() < max_results {
+            let source = match results.next() {
+                None => break,
+                Some(Ok(result)) => {
+                    collected.push(result);
+                    continue;
+                }
+                Some(Err(e)) => e,
+            };
+
+            let partial = match options.error_policy {
+                ErrorPolicy::FailFast => vec![],
+                ErrorPolicy::KeepPartial => collected,
+                ErrorPolicy::SkipFailedPages if !is_fatal(&source) => {
+                    // The failed page is still the current one, so move past
+                    // it rather than retrying it. Failed pages count towards
+                    // the page limit (or, without one, are limited on their
+                    // own), so that a search whose pages all fail still ends.
+                    results.skip_pages(1);
+                    results.max_pages = results.max_pages.map(|max| max.saturating_sub(1));
+                    skipped += 1;
+                    if !limited && skipped == DEFAULT_MAX_PAGES {
+                        break;
+                    }
+                    continue;
+                }
+                ErrorPolicy::SkipFailedPages => collected,
+            };
+
+            return Err(CollectError {
+                partial: partial,
+                source: source,
+            });
+        }
+
+        Ok(collected)
+    }
+}
+
+/// Returns whether `error` affects an entire search, rather than a single page.
+fn is_fatal(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::InvalidQuery { .. }
+            | Error::EmptyQuery
+            | Error::NoResults
+            | Error::Cancelled
+            | Error::CircuitOpen { .. }
+            | Error::CertificatePinMismatch { .. }
+    )
+}
+
+/// Search SunXDCC for the given `query`, collecting its results according to
+/// `options`.
+///
+/// See [`SearchResults::collect_all`] for details.
+///
+/// ```no_run
+/// # use sunxdcc::{CollectOptions, ErrorPolicy};
+/// let options = CollectOptions::new()
+///     .max_results(200)
+///     .error_policy(ErrorPolicy::KeepPartial);
+///
+/// let results = match sunxdcc::collect_all("the hitchhiker's guide", options) {
+///     Ok(results) => results,
+///     Err(e) => {
+///         eprintln!("search failed: {}", e.error());
+///         e.into_parts().0
+///     }
+/// };
+/// ```
+pub fn collect_all(
+    query: &str,
+    options: CollectOptions,
+) -> Result<Vec<SearchResult>, CollectError> {
+    search(query).collect_all(options)
+}
@@ -0,0 +1,582 @@
+//! Downloading packs over DCC, available with the `dcc` feature.
+//!
+//! This builds on the [`irc`](crate::irc) module: it requests a pack, accepts
+//! the bot's `DCC SEND` offer, and downloads the file directly from the bot.
+//!
+//! ```no_run
+//! # use sunxdcc::dcc::{self, DownloadOptions};
+//! # use sunxdcc::irc::RequestOptions;
+//! let result = sunxdcc::search("the hitchhiker's guide to the galaxy")
+//!     .next()
+//!     .unwrap()
+//!     .unwrap();
+//!
+//! let options = DownloadOptions::new(RequestOptions::new("arthurdent"))
+//!     .on_progress(|progress| println!("{}/{:?}", progress.received, progress.total));
+//! let path = dcc::download(&result, "downloads", options).unwrap();
+//! ```
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::irc::{request_target, Connection, IrcRequestError, Message, RequestOptions};
+use crate::SearchResult;
+
+/// How long to wait for the bot's offer, by default.
+const DEFAULT_OFFER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long to wait for data from the bot before giving up.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// The CTCP delimiter.
+const CTCP: char = '\x01';
+
+/// A download progress callback.
+type ProgressCallback = Box<dyn FnMut(&Progress)>;
+
+/// Represents the errors that can occur when downloading a pack.
+#[derive(Debug, Error)]
+pub enum DccError {
+    /// An error occurred while talking to the IRC server.
+    #[error("IRC error")]
+    Irc(#[from] IrcRequestError),
+    /// An I/O error occurred during the transfer.
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+    /// The bot didn't offer the file within the timeout. It may have queued the
+    /// request, or ignored it.
+    #[error("no DCC offer received")]
+    NoOffer,
+    /// The bot offered a passive (reverse) DCC transfer, which requires us to
+    /// listen for its connection. This isn't supported.
+    #[error("passive DCC is not supported")]
+    Passive,
+    /// The bot's offer couldn't be parsed.
+    #[error("malformed DCC offer: {0}")]
+    Malformed(String),
+    /// The bot offered a connection to a loopback, link-local, or private
+    /// address, which is refused unless explicitly allowed (see
+    /// [`DownloadOptions::allow_private_addresses`]).
+    #[error("refusing to connect to private address {0}")]
+    PrivateAddress(SocketAddr),
+    /// The file is larger than the configured maximum size (see
+    /// [`DownloadOptions::max_size`]).
+    #[error("file exceeds the maximum size of {max} bytes")]
+    TooLarge {
+        /// The maximum size, in bytes.
+        max: u64,
+    },
+    /// The bot closed the connection before sending the whole file.
+    #[error("incomplete transfer: received {received} of {expected} bytes")]
+    Incomplete {
+        /// The number of bytes received, including any resumed portion.
+        received: u64,
+        /// The size of the file, as offered.
+        expected: u64,
+    },
+}
+
+/// The progress of a download.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of bytes received so far, including any resumed portion.
+    pub received: u64,
+    /// The size of the file, if the bot offered one.
+    pub total: Option<u64>,
+}
+
+/// Options for downloading a pack.
+pub struct DownloadOptions {
+    /// How to connect to IRC and request the pack.
+    request: RequestOptions,
+    /// How long to wait for the bot's offer.
+    offer_timeout: Duration,
+    /// Whether to resume partial downloads.
+    resume: bool,
+    /// Whether to connect to loopback, link-local, and private addresses.
+    allow_private_addresses: bool,
+    /// The largest file to accept, if limited.
+    max_size: Option<u64>,
+    /// The progress callback, if any.
+    on_progress: Option<ProgressCallback>,
+}
+
+impl fmt::Debug for DownloadOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DownloadOptions")
+            .field("request", &self.request)
+            .field("offer_timeout", &self.offer_timeout)
+            .field("resume", &self.resume)
+            .field("allow_private_addresses", &self.allow_private_addresses)
+            .field("max_size", &self.max_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DownloadOptions {
+    /// Create options that request packs as configured by `request`.
+    pub fn new(request: RequestOptions) -> Self {
+        Self {
+            request: request,
+            offer_timeout: DEFAULT_OFFER_TIMEOUT,
+            resume: true,
+            allow_private_addresses: false,
+            max_size: None,
+            on_progress: None,
+        }
+    }
+
+    /// Set how long to wait for the bot's offer after requesting the pack.
+    /// The default is two minutes.
+    pub fn offer_timeout(mut self, timeout: Duration) -> Self {
+        self.offer_timeout = timeout;
+        self
+    }
+
+    /// Control whether partially downloaded files are resumed with
+    /// `DCC RESUME`, rather than downloaded again from the start. The default
+    /// is to resume.
+    ///
+    /// When resuming is disabled (or the bot declines to resume), an existing
+    /// file with the offered name is truncated and overwritten.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Control whether to accept offers from loopback, link-local, and
+    /// private (e.g. `192.168.0.0/16`) addresses.
+    ///
+    /// Offers come from the bot, so by default these are refused with
+    /// [`DccError::PrivateAddress`] to keep a malicious offer from making us
+    /// connect to hosts on the local network. Allow them when downloading
+    /// from a bot on the same network.
+    pub fn allow_private_addresses(mut self, allow: bool) -> Self {
+        self.allow_private_addresses = allow;
+        self
+    }
+
+    /// Set the largest file to download, in bytes.
+    ///
+    /// Offers for larger files, and transfers that exceed the limit (which
+    /// is how an offer without a size is limited), fail with
+    /// [`DccError::TooLarge`]. By default, there is no limit.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Set a callback to be invoked as data is received.
+    pub fn on_progress(mut self, callback: impl FnMut(&Progress) + 'static) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+}
+
+/// A `DCC SEND` offer.
+#[derive(Debug, PartialEq, Eq)]
+struct Offer {
+    /// The offered filename.
+    filename: String,
+    /// The address to connect to.
+    addr: SocketAddr,
+    /// The file's size, if given.
+    size: Option<u64>,
+}
+
+/// Split a CTCP argument string into words, honoring double-quoted words.
+fn ctcp_words(args: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut rest = args.trim_start();
+
+    while !rest.is_empty() {
+        let (word, tail) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_once(' ').unwrap_or((rest, "")),
+        };
+        words.push(word.to_string());
+        rest = tail.trim_start();
+    }
+
+    words
+}
+
+/// Parse a CTCP `DCC` message body of the given type (e.g. `SEND`), returning
+/// its arguments.
+fn ctcp_dcc<'a>(text: &'a str, kind: &str) -> Option<&'a str> {
+    let body = text.strip_prefix(CTCP)?.trim_end_matches(CTCP);
+    let (dcc, rest) = body.split_once(' ')?;
+    let (found, args) = rest.split_once(' ')?;
+
+    (dcc.eq_ignore_ascii_case("DCC") && found.eq_ignore_ascii_case(kind)).then_some(args)
+}
+
+/// Parse the arguments of a `DCC SEND` offer.
+///
+/// The address may be given as a 32-bit integer (the traditional form) or as
+/// a literal IPv4 or IPv6 address. Sizes over 4 GiB are accepted. Filenames
+/// with control characters are rejected, since we send them back to the bot.
+fn parse_offer(args: &str) -> Result<Offer, DccError> {
+    let malformed = || DccError::Malformed(args.into());
+
+    let words = ctcp_words(args);
+    let [filename, ip, port, rest @ ..] = words.as_slice() else {
+        return Err(malformed());
+    };
+    if filename.contains(char::is_control) {
+        return Err(malformed());
+    }
+
+    let ip = match ip.parse::<u32>() {
+        Ok(ip) => IpAddr::V4(Ipv4Addr::from(ip)),
+        Err(_) => ip.parse().map_err(|_| malformed())?,
+    };
+    let port: u16 = port.parse().map_err(|_| malformed())?;
+    if port == 0 {
+        return Err(DccError::Passive);
+    }
+
+    let size = match rest.first() {
+        Some(size) => Some(size.parse().map_err(|_| malformed())?),
+        None => None,
+    };
+
+    Ok(Offer {
+        filename: filename.clone(),
+        addr: SocketAddr::new(ip, port),
+        size: size,
+    })
+}
+
+/// Returns whether `ip` is a loopback, link-local, private, or otherwise
+/// non-public address.
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Shared address space (100.64.0.0/10), used for carrier-grade NAT.
+                || (a == 100 && b & 0xc0 == 64)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private(IpAddr::V4(ip)),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+            }
+        },
+    }
+}
+
+/// Returns a safe local filename for an offered filename, stripping any
+/// directory components.
+fn local_filename(offered: &str) -> Result<&str, DccError> {
+    match offered.rsplit(['/', '\\']).next() {
+        Some(name) if !name.is_empty() && name != "." && name != ".." => Ok(name),
+        _ => Err(DccError::Malformed(format!("unsafe filename: {offered}"))),
+    }
+}
+
+/// Returns whether `message` is a CTCP message to us from `bot`, returning its
+/// text if so.
+fn ctcp_from<'a>(message: &'a Message, bot: &str, nick: &str) -> Option<&'a str> {
+    let from_bot = message.nick().is_some_and(|n| n.eq_ignore_ascii_case(bot));
+    let to_us = message
+        .params
+        .first()
+        .is_some_and(|target| target.eq_ignore_ascii_case(nick));
+
+    match (message.command.as_str(), from_bot && to_us) {
+        ("PRIVMSG", true) => message.params.get(1).map(String::as_str),
+        _ => None,
+    }
+}
+
+/// Wait for the bot's `DCC SEND` offer.
+fn await_offer(conn: &mut Connection, bot: &str, deadline: Instant) -> Result<Offer, DccError> {
+    loop {
+        let message = conn.recv_until(deadline)?.ok_or(DccError::NoOffer)?;
+        if let Some(args) = ctcp_from(&message, bot, conn.nick()).and_then(|t| ctcp_dcc(t, "SEND"))
+        {
+            return parse_offer(args);
+        }
+    }
+}
+
+/// Ask the bot to resume `offer` from `position`, returning whether it agreed.
+fn negotiate_resume(
+    conn: &mut Connection,
+    bot: &str,
+    offer: &Offer,
+    position: u64,
+    deadline: Instant,
+) -> Result<bool, DccError> {
+    conn.send(&format!(
+        "PRIVMSG {bot} :{CTCP}DCC RESUME \"{}\" {} {position}{CTCP}",
+        offer.filename,
+        offer.addr.port()
+    ))?;
+
+    while let Some(message) = conn.recv_until(deadline)? {
+        let Some(args) = ctcp_from(&message, bot, conn.nick()).and_then(|t| ctcp_dcc(t, "ACCEPT"))
+        else {
+            continue;
+        };
+
+        let words = ctcp_words(args);
+        return Ok(matches!(
+            words.as_slice(),
+            [_, port, accepted, ..]
+                if port.parse() == Ok(offer.addr.port()) && accepted.parse() == Ok(position)
+        ));
+    }
+
+    Ok(false)
+}
+
+/// Receive the file from the bot, appending to `file` from `position`.
+///
+/// Nothing past the offered size (if any) is written, and the transfer fails
+/// once it exceeds `max_size`.
+fn transfer(
+    offer: &Offer,
+    mut file: File,
+    mut received: u64,
+    max_size: Option<u64>,
+    on_progress: &mut Option<ProgressCallback>,
+) -> Result<(), DccError> {
+    let mut stream = TcpStream::connect_timeout(&offer.addr, TRANSFER_TIMEOUT)?;
+    stream.set_read_timeout(Some(TRANSFER_TIMEOUT))?;
+
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let len = match offer.size {
+            Some(size) if received >= size => break,
+            Some(size) => buf
+                .len()
+                .min((size - received).try_into().unwrap_or(usize::MAX)),
+            None => buf.len(),
+        };
+
+        let n = stream.read(&mut buf[..len])?;
+        if n == 0 {
+            break;
+        }
+
+        if let Some(max) = max_size.filter(|max| received + n as u64 > *max) {
+            return Err(DccError::TooLarge { max: max });
+        }
+
+        file.write_all(&buf[..n])?;
+        received += n as u64;
+
+        // Acknowledge everything received so far. The acknowledgement is only
+        // 32 bits wide, so it wraps for files over 4 GiB, as bots expect.
+        stream.write_all(&(received as u32).to_be_bytes())?;
+
+        if let Some(callback) = on_progress {
+            callback(&Progress {
+                received: received,
+                total: offer.size,
+            });
+        }
+    }
+
+    file.flush()?;
+
+    match offer.size {
+        Some(expected) if received < expected => Err(DccError::Incomplete {
+            received: received,
+            expected: expected,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Request `result`'s pack and download it into `dest_dir`, returning the
+/// path of the downloaded file.
+///
+/// The file is named as offered by the bot. If a partial file with that name
+/// already exists, the download is resumed with `DCC RESUME` (unless disabled
+/// in `options`); if the bot declines, the existing file is truncated and
+/// downloaded again from the start.
+///
+/// Offers from private addresses, and files over the configured
+/// [`max_size`](DownloadOptions::max_size), are refused; see
+/// [`DownloadOptions`].
+///
+/// The IRC connection is kept alive for the duration of the transfer, since
+/// many bots cancel transfers for users who leave.
+///
+/// # Limitations
+///
+/// Only active DCC is supported, where we connect to the bot. Passive
+/// (reverse) DCC offers, which require us to accept a connection instead,
+/// produce [`DccError::Passive`].
+pub fn download(
+    result: &SearchResult,
+    dest_dir: impl AsRef<Path>,
+    mut options: DownloadOptions,
+) -> Result<PathBuf, DccError> {
+    let bot: &str = &result.bot;
+
+    let (channel, command) = request_target(result)?;
+
+    let mut conn = Connection::open(result, &options.request)?;
+    conn.join(&channel, options.request.timeout)?;
+    conn.idle(Instant::now() + options.request.join_delay)?;
+    conn.send(&command)?;
+
+    let deadline = Instant::now() + options.offer_timeout;
+    let offer = await_offer(&mut conn, bot, deadline)?;
+
+    if !options.allow_private_addresses && is_private(offer.addr.ip()) {
+        return Err(DccError::PrivateAddress(offer.addr));
+    }
+    if let Some(max) = options.max_size.filter(|max| offer.size > Some(*max)) {
+        return Err(DccError::TooLarge { max: max });
+    }
+
+    let path = dest_dir.as_ref().join(local_filename(&offer.filename)?);
+    let existing = match path.metadata() {
+        Ok(metadata) if options.resume => metadata.len(),
+        _ => 0,
+    };
+
+    if offer.size.is_some_and(|size| existing >= size) {
+        conn.quit()?;
+        return Ok(path);
+    }
+
+    let resumed = existing > 0 && negotiate_resume(&mut conn, bot, &offer, existing, deadline)?;
+    let (file, position) = if resumed {
+        (OpenOptions::new().append(true).open(&path)?, existing)
+    } else {
+        (File::create(&path)?, 0)
+    };
+
+    // Answer pings on another thread while the transfer runs.
+    let done = Arc::new(AtomicBool::new(false));
+    let keepalive = {
+        let done = Arc::clone(&done);
+        thread::spawn(move || -> Result<Connection, IrcRequestError> {
+            while !done.load(Ordering::SeqCst) {
+                conn.idle(Instant::now() + Duration::from_secs(1))?;
+            }
+            Ok(conn)
+        })
+    };
+
+    let transferred = transfer(
+        &offer,
+        file,
+        position,
+        options.max_size,
+        &mut options.on_progress,
+    );
+    done.store(true, Ordering::SeqCst);
+
+    // A lost IRC connection doesn't matter once the transfer has finished.
+    if let Ok(Ok(conn)) = keepalive.join() {
+        let _ = conn.quit();
+    }
+
+    transferred.map(|()| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_ctcp_words() {
+        assert_eq!(ctcp_words("foo.txt 1 2"), ["foo.txt", "1", "2"]);
+        assert_eq!(
+            ctcp_words(r#""my file.txt"  3232235777 5000"#),
+            ["my file.txt", "3232235777", "5000"]
+        );
+        assert_eq!(ctcp_words(r#""unterminated file"#), ["unterminated file"]);
+        assert!(ctcp_words("  ").is_empty());
+    }
+
+    #[test]
+    fn parses_offers() {
+        assert_eq!(
+            parse_offer(r#""foo bar.mkv" 3232235777 5000 6442450944"#).unwrap(),
+            Offer {
+                filename: "foo bar.mkv".into(),
+                addr: "192.168.1.1:5000".parse().unwrap(),
+                size: Some(6442450944),
+            }
+        );
+        assert_eq!(
+            parse_offer("foo.txt ::1 5000").unwrap(),
+            Offer {
+                filename: "foo.txt".into(),
+                addr: "[::1]:5000".parse().unwrap(),
+                size: None,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_offers() {
+        assert!(matches!(
+            parse_offer("foo.txt 3232235777 0 100 token"),
+            Err(DccError::Passive)
+        ));
+        for args in [
+            "foo.txt 3232235777",
+            "foo.txt nope 5000",
+            "foo.txt 1 5000 -1",
+            "\"foo.txt\r\nQUIT\" 3232235777 5000",
+            "foo\x01.txt 3232235777 5000",
+        ] {
+            assert!(matches!(parse_offer(args), Err(DccError::Malformed(_))));
+        }
+    }
+
+    #[test]
+    fn detects_private_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:192.168.1.1",
+        ] {
+            assert!(is_private(ip.parse().unwrap()), "{ip}");
+        }
+
+        for ip in [
+            "8.8.8.8",
+            "100.128.0.1",
+            "2001:4860::8888",
+            "::ffff:8.8.8.8",
+        ] {
+            assert!(!is_private(ip.parse().unwrap()), "{ip}");
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! An on-disk cache of fetched pages, available with the `disk-cache` feature.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use url::Url;
+
+use crate::transport::get_page;
+use crate::{Error, Transport};
+
+/// A counter that makes each temporary file name unique within this process.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Hash the given bytes with 64-bit FNV-1a.
+///
+/// Unlike `std`'s hashers, this is stable across builds, which keeps cache
+/// file names stable across runs.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Returns the current time, in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+/// A transport that caches pages on disk, falling back to another transport
+/// for missing, expired, or corrupt entries.
+///
+/// Each entry is stored as a header of `key: value` lines (the fetch time, the
+/// request URL, and the body length), followed by a blank line and the body.
+#[derive(Debug)]
+pub(crate) struct DiskCacheTransport {
+    /// The transport to fall back to.
+    pub(crate) inner: Box<dyn Transport>,
+    /// The cache directory.
+    pub(crate) dir: PathBuf,
+    /// How long a cached page remains valid.
+    pub(crate) ttl: Duration,
+    /// Whether to ignore existing entries, always fetching (and re-caching) pages.
+    pub(crate) refresh: bool,
+}
+
+impl DiskCacheTransport {
+    fn path(&self, url: &Url) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.page", fnv1a(url.as_str().as_bytes())))
+    }
+
+    /// Load the cached body for `url`, if present, fresh, and intact.
+    fn load(&self, url: &Url) -> Option<Vec<u8>> {
+        let entry = fs::read(self.path(url)).ok()?;
+
+        let split = entry.windows(2).position(|w| w == b"\n\n")?;
+        let (header, body) = (
+            std::str::from_utf8(&entry[..split]).ok()?,
+            &entry[split + 2..],
+        );
+
+        let field = |name: &str| {
+            header
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+        };
+
+        let fetched_at: u64 = field("fetched-at")?.parse().ok()?;
+        let len: usize = field("length")?.parse().ok()?;
+
+        // Guard against hash collisions and truncated writes.
+        if field("url")? != url.as_str() || body.len() != len {
+            return None;
+        }
+
+        let age = Duration::from_secs(now().saturating_sub(fetched_at));
+        (age < self.ttl).then(|| body.to_vec())
+    }
+
+    /// Store `body` as the cached body for `url`.
+    fn store(&self, url: &Url, body: &[u8]) -> Result<(), Error> {
+        let mut entry = format!(
+            "fetched-at: {}\nurl: {url}\nlength: {}\n\n",
+            now(),
+            body.len()
+        )
+        .into_bytes();
+        entry.extend_from_slice(body);
+
+        // Write to a temporary file first, so that readers never observe a
+        // partially written entry. Each writer gets its own temporary file,
+        // so that concurrent writers (in this process or others) can't
+        // publish each other's partial writes.
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(url);
+        let temp = path.with_extension(format!(
+            "{}.{}.tmp",
+            process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let written = fs::write(&temp, entry).and_then(|()| fs::rename(&temp, path));
+        if written.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+
+        Ok(written?)
+    }
+}
+
+impl DiskCacheTransport {
+    /// Serve the page at `url` from the cache if possible, or fetch and cache
+    /// it otherwise.
+    fn fetch(&self, url: &Url, limit: usize, timeout: Option<Duration>) -> Result<Vec<u8>, Error> {
+        if !self.refresh {
+            if let Some(body) = self.load(url).filter(|body| body.len() <= limit) {
+                return Ok(body);
+            }
+        }
+
+        let body = get_page(&*self.inner, url, limit, timeout)?;
+
+        // Caching is best-effort: an unwritable cache directory shouldn't fail
+        // the search.
+        if let Err(_e) = self.store(url, &body) {
+            debug!("failed to cache {url}: {_e}");
+        }
+
+        Ok(body)
+    }
+}
+
+impl Transport for DiskCacheTransport {
+    fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error> {
+        self.fetch(url, limit, None)
+    }
+
+    fn get_page_with_timeout(
+        &self,
+        url: &Url,
+        limit: usize,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        self.fetch(url, limit, Some(timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::thread;
+
+    use super::*;
+    use crate::StaticTransport;
+
+    fn cache(dir: &Path) -> DiskCacheTransport {
+        DiskCacheTransport {
+            inner: Box::new(StaticTransport::default()),
+            dir: dir.into(),
+            ttl: Duration::from_secs(60),
+            refresh: false,
+        }
+    }
+
+    #[test]
+    fn concurrent_stores_never_publish_partial_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = Url::parse("https://sunxdcc.com/deliver.php?sterm=foo&page=0").unwrap();
+
+        // Each writer has its own transport, as separate processes would.
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let (dir, url) = (dir.path().to_path_buf(), url.clone());
+                thread::spawn(move || {
+                    let cache = cache(&dir);
+                    for _ in 0..50 {
+                        cache.store(&url, &vec![i; 64 * 1024]).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        // Whichever write won, the entry is intact, and no temporary files
+        // are left behind.
+        let body = cache(dir.path()).load(&url).unwrap();
+        assert_eq!(body.len(), 64 * 1024);
+        assert!(body.iter().all(|byte| *byte == body[0]));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
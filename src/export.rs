@@ -0,0 +1,190 @@
+//! Helpers for exporting search results to other formats.
+//!
+//! CSV export is available with the `csv` feature, and NDJSON export with the
+//! `serde` feature.
+
+use std::io::{self, Write};
+#[cfg(feature = "csv")]
+use std::{fs::File, path::Path};
+
+use thiserror::Error;
+
+use crate::SearchResult;
+
+/// Represents the errors that can occur when exporting results.
+#[derive(Debug, Error)]
+pub enum ExportError {
+    /// An I/O error occurred while writing.
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+    /// An error occurred while writing CSV.
+    #[cfg(feature = "csv")]
+    #[error("CSV error")]
+    Csv(#[from] csv::Error),
+    /// A result couldn't be serialized as JSON.
+    #[cfg(feature = "serde")]
+    #[error("JSON serialization error")]
+    Json(serde_json::Error),
+}
+
+/// The header row written by [`to_csv`].
+#[cfg(feature = "csv")]
+const CSV_HEADER: [&str; 8] = [
+    "network",
+    "channel",
+    "bot",
+    "filesize",
+    "filename",
+    "packet_number",
+    "download_count",
+    "upload_speed",
+];
+
+/// Write `results` to `w` as CSV, with a header row.
+///
+/// Each result becomes one row, with its fields in declaration order. Fields
+/// are quoted as necessary, and a missing upload speed is written as an
+/// empty cell.
+///
+/// ```
+/// # use sunxdcc::export;
+/// let mut csv = vec![];
+/// export::to_csv(&[], &mut csv).unwrap();
+/// assert!(csv.starts_with(b"network,channel,bot,"));
+/// ```
+#[cfg(feature = "csv")]
+pub fn to_csv<W: Write>(results: &[SearchResult], w: W) -> Result<(), ExportError> {
+    let mut writer = csv::Writer::from_writer(w);
+    writer.write_record(CSV_HEADER)?;
+
+    for result in results {
+        writer.write_record([
+            &*result.network,
+            &*result.channel,
+            &*result.bot,
+            &result.filesize,
+            &result.filename,
+            &result.packet_number,
+            &result.download_count,
+            result.upload_speed.as_deref().unwrap_or_default(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write `results` as CSV to the file at `path`, creating or truncating it.
+///
+/// See [`to_csv`] for details.
+#[cfg(feature = "csv")]
+pub fn write_csv_file(results: &[SearchResult], path: impl AsRef<Path>) -> Result<(), ExportError> {
+    to_csv(results, File::create(path)?)
+}
+
+/// Write `results` to `w` as newline-delimited JSON (NDJSON), one compact
+/// object per line.
+///
+/// Objects use the `serde` representation of [`SearchResult`], so a missing
+/// upload speed is written as `null`. `w` is flushed after each line, so
+/// output can be consumed as it's written (see also
+/// `SearchResults::write_jsonl`, which writes results as they're fetched):
+///
+/// ```no_run
+/// # use std::io;
+/// # use sunxdcc::export;
+/// let results = sunxdcc::search("the hitchhiker's guide to the galaxy")
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// export::to_ndjson(&results, io::stdout().lock()).unwrap();
+/// ```
+#[cfg(feature = "serde")]
+pub fn to_ndjson<'a, W: Write>(
+    results: impl IntoIterator<Item = &'a SearchResult>,
+    mut w: W,
+) -> Result<(), ExportError> {
+    for result in results {
+        serde_json::to_writer(&mut w, result).map_err(|e| {
+            if e.is_io() {
+                ExportError::Io(e.into())
+            } else {
+                ExportError::Json(e)
+            }
+        })?;
+        w.write_all(b"\n")?;
+        w.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(filename: &str, upload_speed: Option<&str>) -> SearchResult {
+        SearchResult {
+            network: "irc.foo.net".into(),
+            channel: "#foo".into(),
+            bot: "foobot".into(),
+            filesize: "[1M]".into(),
+            filename: filename.into(),
+            packet_number: "#1".into(),
+            download_count: "0x".into(),
+            upload_speed: upload_speed.map(Into::into),
+        }
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn csv_round_trips() {
+        let results = [
+            result(r#"foo, "bar".mkv"#, Some("10kB/s")),
+            result("plain.txt", None),
+        ];
+        let mut csv = vec![];
+        to_csv(&results, &mut csv).unwrap();
+
+        let mut reader = csv::Reader::from_reader(csv.as_slice());
+        assert_eq!(reader.headers().unwrap(), CSV_HEADER.as_slice());
+
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][4], r#"foo, "bar".mkv"#);
+        assert_eq!(&rows[0][7], "10kB/s");
+        assert_eq!(&rows[1][4], "plain.txt");
+        assert_eq!(&rows[1][7], "");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ndjson_writes_one_object_per_line() {
+        let results = [
+            result(r#"foo, "bar".mkv"#, Some("10kB/s")),
+            result("multi\nline.txt", None),
+            result("plain.txt", None),
+        ];
+        let mut ndjson = vec![];
+        to_ndjson(&results, &mut ndjson).unwrap();
+
+        let ndjson = String::from_utf8(ndjson).unwrap();
+        assert!(ndjson.ends_with('\n'));
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["filename"], r#"foo, "bar".mkv"#);
+        assert_eq!(lines[0]["upload_speed"], "10kB/s");
+        assert_eq!(lines[1]["filename"], "multi\nline.txt");
+        assert_eq!(lines[1]["upload_speed"], serde_json::Value::Null);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ndjson_writes_nothing_for_no_results() {
+        let mut ndjson = vec![];
+        to_ndjson(&[], &mut ndjson).unwrap();
+        assert!(ndjson.is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+//! Newtype wrappers for the string fields of search results.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use crate::Error;
+
+/// Define a newtype wrapper around `String`, with the common conversions.
+macro_rules! string_newtype {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde", serde(transparent))]
+        pub struct $name(String);
+
+        impl $name {
+            /// Returns this value as a string slice.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                Self(value.into())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+string_newtype!(
+    /// An IRC network, e.g. `irc.foo.net`.
+    Network
+);
+
+string_newtype!(
+    /// An IRC channel, e.g. `#foo`.
+    ///
+    /// Parsing a `Channel` via [`FromStr`] requires the leading `#`; the
+    /// infallible `From` conversions do not validate their input.
+    Channel
+);
+
+string_newtype!(
+    /// An IRC bot's nickname.
+    Bot
+);
+
+impl FromStr for Network {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+impl FromStr for Channel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('#') {
+            Some(name) if !name.is_empty() => Ok(s.into()),
+            _ => Err(Error::Malformed(format!("invalid channel: {s:?}"))),
+        }
+    }
+}
+
+impl FromStr for Bot {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+/// Split a raw channel field into its channels, normalizing each one.
+///
+/// See [`SearchResult::channels`](crate::SearchResult::channels).
+pub(crate) fn split_channels(raw: &str) -> Vec<Channel> {
+    strip_formatting(raw)
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|name| {
+            let name = name.to_ascii_lowercase();
+            match name.chars().next() {
+                None => None,
+                Some('#' | '&' | '+' | '!') if name.len() == 1 => None,
+                Some('#' | '&' | '+' | '!') => Some(name.into()),
+                Some(_) => Some(format!("#{name}").into()),
+            }
+        })
+        .collect()
+}
+
+/// Strip mIRC-style formatting codes (bold, colors, and so on) from `s`.
+fn strip_formatting(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            // Colors are followed by up to two digits of foreground, and
+            // optionally a comma and up to two digits of background.
+            '\x03' => {
+                rest = skip_color(rest);
+                if let Some(background) = rest
+                    .strip_prefix(',')
+                    .filter(|bg| bg.starts_with(|c: char| c.is_ascii_digit()))
+                {
+                    rest = skip_color(background);
+                }
+            }
+            c if c.is_whitespace() => stripped.push(c),
+            c if c.is_control() => {}
+            c => stripped.push(c),
+        }
+    }
+
+    stripped
+}
+
+/// Skip the (up to two digit) color number at the start of `s`.
+fn skip_color(s: &str) -> &str {
+    let digits = s.bytes().take(2).take_while(u8::is_ascii_digit).count();
+    &s[digits..]
+}
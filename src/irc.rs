@@ -0,0 +1,683 @@
+//! Requesting packs over IRC, available with the `irc` feature.
+//!
+//! This is a minimal, blocking IRC client: it connects to a result's network,
+//! joins its channel, and asks its bot for the pack. It doesn't receive the
+//! resulting file; see the `dcc` feature for that.
+//!
+//! The client is deliberately hand-rolled rather than built on the `irc`
+//! crate: requesting a pack needs only a handful of commands (`NICK`, `USER`,
+//! `JOIN`, `PRIVMSG`, and answering `PING`s), whereas the `irc` crate requires
+//! an async runtime and pulls in a large dependency tree for a feature that
+//! most users of a search wrapper never enable.
+//!
+//! ```no_run
+//! # use sunxdcc::irc::{self, RequestOptions};
+//! # use std::time::Duration;
+//! let result = sunxdcc::search("the hitchhiker's guide to the galaxy")
+//!     .next()
+//!     .unwrap()
+//!     .unwrap();
+//!
+//! let options = RequestOptions::new("arthurdent").join_delay(Duration::from_secs(30));
+//! irc::request_pack(&result, &options).unwrap();
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::{Channel, SearchResult};
+
+/// How long to wait for the server to accept our connection and registration,
+/// by default.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Represents the errors that can occur when requesting a pack over IRC.
+#[derive(Debug, Error)]
+pub enum IrcRequestError {
+    /// An I/O error occurred while talking to the server.
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+    /// A TLS connection to the server couldn't be established.
+    #[error("TLS error")]
+    Tls(#[from] native_tls::Error),
+    /// The result's network isn't a valid host and port.
+    #[error("invalid network: {0}")]
+    InvalidNetwork(String),
+    /// A field can't be sent to the server safely, e.g. because it contains
+    /// line breaks that would inject other commands.
+    #[error("invalid {field} for IRC: {value:?}")]
+    InvalidField {
+        /// The name of the field.
+        field: &'static str,
+        /// The field's value.
+        value: String,
+    },
+    /// The server rejected us, e.g. because our nickname is taken or the
+    /// channel can't be joined.
+    #[error("server error: {0}")]
+    Server(String),
+    /// The server didn't complete registration or the join within the timeout.
+    #[error("timed out waiting for the server")]
+    TimedOut,
+    /// The server closed the connection unexpectedly.
+    #[error("disconnected by the server")]
+    Disconnected,
+}
+
+/// Options for connecting to IRC and requesting a pack.
+#[derive(Clone, Debug)]
+pub struct RequestOptions {
+    /// The nickname to connect with.
+    pub(crate) nick: String,
+    /// Whether to connect with TLS.
+    pub(crate) tls: bool,
+    /// The port to connect to, if overridden.
+    pub(crate) port: Option<u16>,
+    /// How long to sit in the channel before requesting.
+    pub(crate) join_delay: Duration,
+    /// How long to wait for registration and the join.
+    pub(crate) timeout: Duration,
+}
+
+impl RequestOptions {
+    /// Create options for connecting with the given nickname, without TLS
+    /// and without a join delay.
+    pub fn new(nick: impl Into<String>) -> Self {
+        Self {
+            nick: nick.into(),
+            tls: false,
+            port: None,
+            join_delay: Duration::ZERO,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Control whether to connect with TLS.
+    ///
+    /// Most networks use a different port for TLS (commonly 6697), which must
+    /// be set with [`port`](RequestOptions::port).
+    pub fn tls(mut self, tls: bool) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Override the port to connect to.
+    ///
+    /// By default, the port is taken from the result's network (see
+    /// [`SearchResult::network_port`]).
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Set how long to stay in the channel before requesting the pack.
+    ///
+    /// Many channels only allow requests from users who have been present for
+    /// some time. The default is no delay.
+    pub fn join_delay(mut self, delay: Duration) -> Self {
+        self.join_delay = delay;
+        self
+    }
+
+    /// Set how long to wait for the server to accept registration and the
+    /// channel join. The default is 60 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Returns the IRC command that requests `result`'s pack from its bot.
+///
+/// This is useful for requesting packs with your own IRC client. Since
+/// results come from the API, the bot and packet number are checked first:
+/// fields that would break the command into several (or otherwise change it)
+/// produce [`IrcRequestError::InvalidField`].
+///
+/// ```
+/// # use sunxdcc::{irc, SearchResult};
+/// let result = SearchResult {
+///     network: "irc.foo.net".into(),
+///     channel: "#foo".into(),
+///     bot: "foobot".into(),
+///     filesize: "[1M]".into(),
+///     filename: "foo.txt".into(),
+///     packet_number: "#12".into(),
+///     download_count: "0x".into(),
+///     upload_speed: None,
+/// };
+/// assert_eq!(
+///     irc::request_command(&result).unwrap(),
+///     "PRIVMSG foobot :xdcc send #12"
+/// );
+/// ```
+pub fn request_command(result: &SearchResult) -> Result<String, IrcRequestError> {
+    Ok(format!(
+        "PRIVMSG {} :xdcc send {}",
+        check_param("bot", &result.bot)?,
+        check_trailing("packet number", &result.packet_number)?
+    ))
+}
+
+/// Returns the channel to join and the command to send to request `result`'s
+/// pack, checking both before anything is sent.
+pub(crate) fn request_target(result: &SearchResult) -> Result<(Channel, String), IrcRequestError> {
+    let channel = result
+        .primary_channel()
+        .ok_or_else(|| IrcRequestError::InvalidField {
+            field: "channel",
+            value: result.channel.to_string(),
+        })?;
+    check_param("channel", &channel)?;
+
+    Ok((channel, request_command(result)?))
+}
+
+/// Returns `value` if it can be sent as a single, non-trailing parameter.
+fn check_param<'a>(field: &'static str, value: &'a str) -> Result<&'a str, IrcRequestError> {
+    match value.chars().next() {
+        Some(first) if first != ':' && !value.contains([' ', ',']) => check_trailing(field, value),
+        _ => Err(IrcRequestError::InvalidField {
+            field: field,
+            value: value.into(),
+        }),
+    }
+}
+
+/// Returns `value` if it can be sent as (part of) a trailing parameter,
+/// i.e. if it has no control characters.
+pub(crate) fn check_trailing<'a>(
+    field: &'static str,
+    value: &'a str,
+) -> Result<&'a str, IrcRequestError> {
+    if value.contains(char::is_control) {
+        return Err(IrcRequestError::InvalidField {
+            field: field,
+            value: value.into(),
+        });
+    }
+
+    Ok(value)
+}
+
+/// A parsed IRC message.
+#[derive(Debug)]
+pub(crate) struct Message {
+    /// The message's source, if any.
+    pub(crate) prefix: Option<String>,
+    /// The command or numeric reply.
+    pub(crate) command: String,
+    /// The command's parameters, including any trailing parameter.
+    pub(crate) params: Vec<String>,
+}
+
+impl Message {
+    /// Parse a single IRC message, without its line ending.
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let mut rest = line;
+
+        let prefix = match rest.strip_prefix(':') {
+            Some(tail) => {
+                let (prefix, tail) = tail.split_once(' ')?;
+                rest = tail;
+                Some(prefix.to_string())
+            }
+            None => None,
+        };
+
+        let (middle, trailing) = match rest.split_once(" :") {
+            Some((middle, trailing)) => (middle, Some(trailing)),
+            None => (rest, None),
+        };
+
+        let mut words = middle.split(' ').filter(|w| !w.is_empty());
+        let command = words.next()?.to_ascii_uppercase();
+        let mut params: Vec<String> = words.map(String::from).collect();
+        params.extend(trailing.map(String::from));
+
+        Some(Self {
+            prefix: prefix,
+            command: command,
+            params: params,
+        })
+    }
+
+    /// Returns the nickname portion of this message's prefix, if any.
+    pub(crate) fn nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_deref()?;
+        Some(prefix.split_once('!').map_or(prefix, |(nick, _)| nick))
+    }
+}
+
+/// A plain or TLS connection to a server.
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<native_tls::TlsStream<TcpStream>>),
+}
+
+impl Stream {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Stream::Plain(stream) => stream,
+            Stream::Tls(stream) => stream.get_ref(),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// A registered connection to an IRC server.
+pub(crate) struct Connection {
+    /// The underlying stream.
+    stream: BufReader<Stream>,
+    /// The partially read line, kept across read timeouts.
+    line: Vec<u8>,
+    /// Our nickname.
+    nick: String,
+}
+
+impl Connection {
+    /// Connect to `result`'s network and register with the server.
+    pub(crate) fn open(
+        result: &SearchResult,
+        options: &RequestOptions,
+    ) -> Result<Self, IrcRequestError> {
+        check_param("nickname", &options.nick)?;
+
+        let host = result
+            .network_host_checked()
+            .map_err(|_| IrcRequestError::InvalidNetwork(result.network.to_string()))?;
+        let port = match options.port {
+            Some(port) => port,
+            None => result
+                .network_port()
+                .ok_or_else(|| IrcRequestError::InvalidNetwork(result.network.to_string()))?,
+        };
+
+        let mut last_error = None;
+        let mut tcp = None;
+        for addr in (host, port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, options.timeout) {
+                Ok(stream) => {
+                    tcp = Some(stream);
+                    break;
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        let tcp = match (tcp, last_error) {
+            (Some(tcp), _) => tcp,
+            (None, Some(e)) => return Err(e.into()),
+            (None, None) => return Err(IrcRequestError::InvalidNetwork(host.into())),
+        };
+
+        let stream = if options.tls {
+            let tls = native_tls::TlsConnector::new()?
+                .connect(host, tcp)
+                .map_err(|e| match e {
+                    native_tls::HandshakeError::Failure(e) => IrcRequestError::Tls(e),
+                    native_tls::HandshakeError::WouldBlock(_) => {
+                        IrcRequestError::Io(io::ErrorKind::WouldBlock.into())
+                    }
+                })?;
+            Stream::Tls(Box::new(tls))
+        } else {
+            Stream::Plain(tcp)
+        };
+
+        let mut conn = Self {
+            stream: BufReader::new(stream),
+            line: vec![],
+            nick: options.nick.clone(),
+        };
+
+        conn.send(&format!("NICK {}", options.nick))?;
+        conn.send(&format!("USER {} 0 * :{}", options.nick, options.nick))?;
+
+        let deadline = Instant::now() + options.timeout;
+        loop {
+            let message = conn
+                .recv_until(deadline)?
+                .ok_or(IrcRequestError::TimedOut)?;
+            match message.command.as_str() {
+                // RPL_WELCOME: we're registered.
+                "001" => break,
+                "ERROR" => return Err(server_error(&message)),
+                numeric if is_error_reply(numeric) => return Err(server_error(&message)),
+                _ => {}
+            }
+        }
+
+        Ok(conn)
+    }
+
+    /// Returns our nickname.
+    pub(crate) fn nick(&self) -> &str {
+        &self.nick
+    }
+
+    /// Send a single IRC message.
+    ///
+    /// Messages containing line breaks or NULs are rejected, since they would
+    /// be read as several messages.
+    pub(crate) fn send(&mut self, message: &str) -> Result<(), IrcRequestError> {
+        if message.contains(['\r', '\n', '\0']) {
+            return Err(IrcRequestError::InvalidField {
+                field: "message",
+                value: message.into(),
+            });
+        }
+
+        let stream = self.stream.get_mut();
+        stream.write_all(message.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    /// Join `channel`, waiting until the server confirms the join.
+    pub(crate) fn join(&mut self, channel: &str, timeout: Duration) -> Result<(), IrcRequestError> {
+        check_param("channel", channel)?;
+        self.send(&format!("JOIN {channel}"))?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let message = self
+                .recv_until(deadline)?
+                .ok_or(IrcRequestError::TimedOut)?;
+            match message.command.as_str() {
+                "JOIN"
+                    if message.nick() == Some(self.nick())
+                        && message
+                            .params
+                            .first()
+                            .is_some_and(|joined| joined.eq_ignore_ascii_case(channel)) =>
+                {
+                    return Ok(())
+                }
+                "ERROR" => return Err(server_error(&message)),
+                numeric if is_error_reply(numeric) => return Err(server_error(&message)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Stay connected until `until`, answering pings in the meantime.
+    pub(crate) fn idle(&mut self, until: Instant) -> Result<(), IrcRequestError> {
+        while let Some(message) = self.recv_until(until)? {
+            if message.command == "ERROR" {
+                return Err(server_error(&message));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Receive the next message, answering pings, or `None` if `deadline`
+    /// passes first.
+    pub(crate) fn recv_until(
+        &mut self,
+        deadline: Instant,
+    ) -> Result<Option<Message>, IrcRequestError> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            self.stream
+                .get_ref()
+                .tcp()
+                .set_read_timeout(Some(remaining))?;
+
+            match self.stream.read_until(b'\n', &mut self.line) {
+                Ok(0) => return Err(IrcRequestError::Disconnected),
+                Ok(_) if !self.line.ends_with(b"\n") => return Err(IrcRequestError::Disconnected),
+                Ok(_) => {}
+                // Partial lines are kept in our buffer until the next read.
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    continue
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            let line = String::from_utf8_lossy(&self.line)
+                .trim_end_matches(['\r', '\n'])
+                .to_string();
+            self.line.clear();
+
+            let Some(message) = Message::parse(&line) else {
+                continue;
+            };
+
+            if message.command == "PING" {
+                let token = message.params.first().map_or("", String::as_str);
+                self.send(&format!("PONG :{token}"))?;
+                continue;
+            }
+
+            return Ok(Some(message));
+        }
+    }
+
+    /// Disconnect from the server.
+    pub(crate) fn quit(mut self) -> Result<(), IrcRequestError> {
+        self.send("QUIT")
+    }
+}
+
+/// Returns whether `command` is an error reply that ends registration or a
+/// join: nickname errors, and channel errors such as bans or invite-only.
+fn is_error_reply(command: &str) -> bool {
+    matches!(
+        command,
+        "403"
+            | "405"
+            | "431"
+            | "432"
+            | "433"
+            | "436"
+            | "437"
+            | "465"
+            | "471"
+            | "473"
+            | "474"
+            | "475"
+            | "477"
+    )
+}
+
+/// Build an error from a server's error message.
+fn server_error(message: &Message) -> IrcRequestError {
+    let text = message.params.last().cloned().unwrap_or_default();
+    IrcRequestError::Server(format!("{}: {text}", message.command))
+}
+
+/// Connect to `result`'s network, join its channel, and request its pack from
+/// its bot, then disconnect.
+///
+/// Only the result's [`primary_channel`](SearchResult::primary_channel) is
+/// joined. Results whose bot, channel, or packet number can't be sent safely
+/// produce [`IrcRequestError::InvalidField`] before connecting.
+///
+/// This blocks for at least the configured
+/// [`join_delay`](RequestOptions::join_delay). The bot's response (such as a
+/// DCC SEND offer) isn't awaited.
+pub fn request_pack(
+    result: &SearchResult,
+    options: &RequestOptions,
+) -> Result<(), IrcRequestError> {
+    let (channel, command) = request_target(result)?;
+
+    let mut conn = Connection::open(result, options)?;
+    conn.join(&channel, options.timeout)?;
+    conn.idle(Instant::now() + options.join_delay)?;
+    conn.send(&command)?;
+    conn.quit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_messages() {
+        let message =
+            Message::parse(":foobot!~bot@example.com PRIVMSG arthurdent :hello there").unwrap();
+        assert_eq!(message.prefix.as_deref(), Some("foobot!~bot@example.com"));
+        assert_eq!(message.nick(), Some("foobot"));
+        assert_eq!(message.command, "PRIVMSG");
+        assert_eq!(message.params, ["arthurdent", "hello there"]);
+
+        let message = Message::parse("ping :irc.foo.net").unwrap();
+        assert_eq!(message.prefix, None);
+        assert_eq!(message.nick(), None);
+        assert_eq!(message.command, "PING");
+        assert_eq!(message.params, ["irc.foo.net"]);
+
+        let message = Message::parse(":irc.foo.net 001  arthurdent  :Welcome :)").unwrap();
+        assert_eq!(message.nick(), Some("irc.foo.net"));
+        assert_eq!(message.command, "001");
+        assert_eq!(message.params, ["arthurdent", "Welcome :)"]);
+
+        let message = Message::parse(":arthurdent JOIN #foo").unwrap();
+        assert_eq!(message.params, ["#foo"]);
+    }
+
+    #[test]
+    fn rejects_empty_messages() {
+        assert!(Message::parse("").is_none());
+        assert!(Message::parse(":irc.foo.net").is_none());
+        assert!(Message::parse(":irc.foo.net ").is_none());
+    }
+
+    #[test]
+    fn builds_request_commands() {
+        let result = SearchResult {
+            network: "irc.foo.net".into(),
+            channel: "#foo".into(),
+            bot: "[foo]bot".into(),
+            filesize: "[1M]".into(),
+            filename: "foo bar.txt".into(),
+            packet_number: "#1234".into(),
+            download_count: "0x".into(),
+            upload_speed: None,
+        };
+        assert_eq!(
+            request_command(&result).unwrap(),
+            "PRIVMSG [foo]bot :xdcc send #1234"
+        );
+    }
+
+    #[test]
+    fn rejects_injected_fields() {
+        let result = SearchResult {
+            network: "irc.foo.net".into(),
+            channel: "#foo #bar".into(),
+            bot: "foobot".into(),
+            filesize: "[1M]".into(),
+            filename: "foo.txt".into(),
+            packet_number: "#1".into(),
+            download_count: "0x".into(),
+            upload_speed: None,
+        };
+        let (channel, _) = request_target(&result).unwrap();
+        assert_eq!(&*channel, "#foo");
+
+        // Line breaks in the channel field separate channels.
+        let result = SearchResult {
+            channel: "#foo\r\nQUIT".into(),
+            ..result
+        };
+        let (channel, _) = request_target(&result).unwrap();
+        assert_eq!(&*channel, "#foo");
+
+        for bot in [
+            "",
+            "foo bot",
+            ":foobot",
+            "foo,bot",
+            "foobot\r\nQUIT",
+            "foo\0bot",
+        ] {
+            let result = SearchResult {
+                bot: bot.into(),
+                ..result.clone()
+            };
+            assert!(matches!(
+                request_command(&result),
+                Err(IrcRequestError::InvalidField { field: "bot", .. })
+            ));
+        }
+
+        for packet_number in ["#1\r\nJOIN #evil", "#1\n", "#1\0"] {
+            let result = SearchResult {
+                packet_number: packet_number.into(),
+                ..result.clone()
+            };
+            assert!(matches!(
+                request_command(&result),
+                Err(IrcRequestError::InvalidField {
+                    field: "packet number",
+                    ..
+                })
+            ));
+        }
+
+        for channel in ["", " , ", "#"] {
+            let result = SearchResult {
+                channel: channel.into(),
+                ..result.clone()
+            };
+            assert!(matches!(
+                request_target(&result),
+                Err(IrcRequestError::InvalidField {
+                    field: "channel",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn builds_server_errors() {
+        let message =
+            Message::parse(":irc.foo.net 433 * arthurdent :Nickname is already in use").unwrap();
+        assert!(is_error_reply(&message.command));
+        assert!(matches!(
+            server_error(&message),
+            IrcRequestError::Server(text) if text == "433: Nickname is already in use"
+        ));
+        assert!(!is_error_reply("001"));
+    }
+}
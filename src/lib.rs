@@ -5,25 +5,452 @@
 #![allow(clippy::redundant_field_names)]
 #![forbid(unsafe_code)]
 
-use itertools::izip;
+use std::cmp;
+#[cfg(feature = "blocking")]
+use std::collections::{BTreeMap, HashMap, HashSet};
+#[cfg(feature = "blocking")]
+use std::env;
+use std::fmt;
+use std::io;
+#[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
+use std::iter::zip;
+#[cfg(feature = "blocking")]
+use std::iter::{self, FusedIterator};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::ops::Deref;
+#[cfg(feature = "blocking")]
+use std::path::PathBuf;
+use std::str::FromStr;
+#[cfg(feature = "blocking")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "blocking")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "blocking")]
+use std::thread;
+use std::time::{Duration, Instant};
+use std::vec;
+
+#[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
 use serde::Deserialize;
 use thiserror::Error;
-use url::Url;
+use url::{form_urlencoded, Url};
+
+#[cfg(feature = "blocking")]
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
+
+/// Log a message at the debug level, if the `logging` feature is enabled.
+#[cfg(feature = "blocking")]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "logging")]
+        log::debug!($($arg)*);
+    };
+}
 
+#[cfg(feature = "async")]
+mod async_search;
+#[cfg(feature = "blocking")]
+mod cache;
+#[cfg(feature = "blocking")]
+mod collect;
+#[cfg(feature = "dcc")]
+pub mod dcc;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
+#[cfg(any(feature = "csv", feature = "serde"))]
+pub mod export;
+mod fields;
+#[cfg(feature = "irc")]
+pub mod irc;
+mod normalize;
+mod result_set;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod transport;
+mod units;
+
+#[cfg(feature = "async")]
+pub use async_search::{search_async, search_page, AsyncSearchResults, LimiterFuture};
+#[cfg(feature = "blocking")]
+pub use cache::{CacheConfig, PageCache};
+#[cfg(feature = "blocking")]
+pub use collect::{collect_all, CollectError, CollectOptions, ErrorPolicy};
+use fields::split_channels;
+pub use fields::{Bot, Channel, Network};
+#[cfg(feature = "blocking")]
+use normalize::collapse_whitespace;
+pub use normalize::normalize_query;
+#[cfg(feature = "unicode")]
+pub use normalize::strip_diacritics;
+pub use result_set::SearchResultSet;
+#[cfg(feature = "blocking")]
+use transport::{RecordingTransport, ReplayTransport};
+pub use transport::{StaticTransport, Transport};
+pub use units::{FileSize, UploadSpeed};
+
+#[cfg(any(feature = "async", feature = "blocking"))]
 const BASE_URL: &str = "https://sunxdcc.com/deliver.php";
 
+/// SunXDCC's human-facing search page.
+const SEARCH_PAGE_URL: &str = "https://sunxdcc.com/";
+
+/// Returns the default API endpoint.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn default_base_url() -> Url {
+    // Unwrap safety: BASE_URL is a correct URL.
+    #[allow(clippy::unwrap_used)]
+    Url::parse(BASE_URL).unwrap()
+}
+
+/// How spaces in search queries are encoded in request URLs.
+///
+/// In both encodings, reserved characters such as `&`, `=`, `#`, and `+`
+/// are percent-encoded, as are all non-ASCII characters (as UTF-8).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueryEncoding {
+    /// Encode spaces as `+`, as in HTML form submissions. This is what
+    /// SunXDCC's own search page does.
+    #[default]
+    Plus,
+    /// Encode spaces as `%20`.
+    Percent,
+}
+
+impl QueryEncoding {
+    /// Encode `query` for use as the value of the API's `sterm` parameter.
+    ///
+    /// ```
+    /// # use sunxdcc::QueryEncoding;
+    /// assert_eq!(QueryEncoding::Plus.encode("foo bar"), "foo+bar");
+    /// assert_eq!(QueryEncoding::Percent.encode("foo bar"), "foo%20bar");
+    /// assert_eq!(
+    ///     QueryEncoding::Plus.encode("c++ & c# = 100%"),
+    ///     "c%2B%2B+%26+c%23+%3D+100%25"
+    /// );
+    /// ```
+    pub fn encode(self, query: &str) -> String {
+        let encoded: String = form_urlencoded::byte_serialize(query.as_bytes()).collect();
+        match self {
+            Self::Plus => encoded,
+            // Literal `+`s are already encoded as `%2B`, so any remaining `+` is a space.
+            Self::Percent => encoded.replace('+', "%20"),
+        }
+    }
+}
+
+/// Build the URL for the given page of results for `query`.
+///
+/// The query parameters are appended to `base`, preserving any that it
+/// already has.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn page_url(base: &Url, query: &str, page: usize, encoding: QueryEncoding) -> Url {
+    let params = format!("sterm={}&page={page}", encoding.encode(query));
+    let mut url = base.clone();
+    match base.query() {
+        Some(existing) if !existing.is_empty() => {
+            url.set_query(Some(&format!("{existing}&{params}")))
+        }
+        _ => url.set_query(Some(&params)),
+    }
+
+    url
+}
+
+/// The standard IRC port.
+const DEFAULT_IRC_PORT: u16 = 6667;
+
+/// The maximum number of results returned in a single page.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const PAGE_SIZE: usize = 50;
+
+/// The default `Accept` header sent with API requests.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const DEFAULT_ACCEPT: &str = "application/json";
+
+/// The default maximum number of pages fetched by a single search.
+#[cfg(feature = "blocking")]
+const DEFAULT_MAX_PAGES: usize = 100;
+
+/// The default minimum length of a search query, in characters.
+#[cfg(feature = "blocking")]
+const DEFAULT_MIN_QUERY_LEN: usize = 2;
+
+/// The default maximum length of a search query, in characters.
+#[cfg(feature = "blocking")]
+const DEFAULT_MAX_QUERY_LEN: usize = 256;
+
+/// The default maximum size of a single response body, in bytes.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+
+/// How often to check for cancellation while sleeping.
+#[cfg(feature = "blocking")]
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The delay before retrying a rate-limited request, when the server doesn't
+/// supply a `Retry-After`.
+#[cfg(feature = "blocking")]
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The timeout for each request, when not overridden. This matches
+/// `reqwest`'s own default.
+#[cfg(feature = "blocking")]
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The delay before re-fetching an empty page, when tolerating empty pages.
+#[cfg(feature = "blocking")]
+const EMPTY_PAGE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
 /// Represents the errors that can occur when retrieving search results.
+///
+/// Errors that wrap an underlying error (`Request` and `Io`) expose it via
+/// [`std::error::Error::source`], for use in structured error reporting.
 #[derive(Debug, Error)]
 pub enum Error {
     /// An request error occurred.
     #[error("request error")]
+    #[cfg(any(feature = "async", feature = "blocking"))]
     Request(#[from] reqwest::Error),
     /// A response contains malformed results.
     #[error("malformed response: {0}")]
     Malformed(String),
+    /// An I/O error occurred while reading a response.
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+    /// A response body exceeded the configured size limit.
+    #[error("response too large: limit is {limit} bytes, got at least {got_at_least}")]
+    ResponseTooLarge {
+        /// The configured limit, in bytes.
+        limit: usize,
+        /// The number of bytes read before giving up.
+        got_at_least: usize,
+    },
+    /// The API rate-limited a request, and no retries remained.
+    #[error("rate limited")]
+    RateLimited {
+        /// The delay requested by the server before retrying, if supplied.
+        retry_after: Option<Duration>,
+    },
+    /// The circuit breaker is open, and no requests will be made until it closes.
+    #[error("circuit breaker open")]
+    CircuitOpen {
+        /// The time at which a request will next be attempted.
+        retry_at: Instant,
+    },
+    /// A query was empty, or contained only whitespace.
+    #[error("empty query")]
+    EmptyQuery,
+    /// The search was cancelled while waiting to retry a request.
+    #[error("search cancelled")]
+    Cancelled,
+    /// The search's deadline passed while waiting to retry a request.
+    #[error("search deadline exceeded")]
+    DeadlineExceeded,
+    /// A configured base URL is invalid.
+    #[error("invalid base URL: {0}")]
+    InvalidBaseUrl(String),
+    /// A configured HTTP header value is invalid.
+    #[error("invalid header value: {0}")]
+    InvalidHeader(String),
+    /// An environment variable consulted for configuration has an invalid
+    /// value.
+    #[error("invalid value for environment variable {name}: {value:?}")]
+    InvalidEnvVar {
+        /// The name of the variable.
+        name: &'static str,
+        /// The variable's value, lossily converted to UTF-8 if necessary.
+        value: String,
+    },
+    /// The search returned no results, and was configured to treat that as an
+    /// error.
+    #[error("no results")]
+    NoResults,
+    /// A query was rejected before any request was made.
+    #[error("invalid query: {reason}")]
+    InvalidQuery {
+        /// Why the query was rejected.
+        reason: String,
+    },
+    /// A configured certificate or certificate fingerprint is invalid.
+    #[error("invalid certificate: {0}")]
+    InvalidCertificate(String),
+    /// The server's certificate doesn't match any pinned fingerprint, so its
+    /// response was discarded.
+    #[error("certificate pin mismatch")]
+    CertificatePinMismatch {
+        /// The SHA-256 fingerprint of the server's certificate, in hex, or
+        /// `None` if the connection didn't use TLS.
+        fingerprint: Option<String>,
+    },
+}
+
+/// Errors are compared structurally, with two exceptions: `Request` errors are
+/// never equal to each other (since `reqwest::Error` has no notion of equality),
+/// and `Io` errors are equal when their [`ErrorKind`](std::io::ErrorKind)s match.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            #[cfg(any(feature = "async", feature = "blocking"))]
+            (Self::Request(_), Self::Request(_)) => false,
+            (Self::Malformed(a), Self::Malformed(b)) => a == b,
+            (Self::Io(a), Self::Io(b)) => a.kind() == b.kind(),
+            (
+                Self::ResponseTooLarge {
+                    limit: a_limit,
+                    got_at_least: a_got,
+                },
+                Self::ResponseTooLarge {
+                    limit: b_limit,
+                    got_at_least: b_got,
+                },
+            ) => a_limit == b_limit && a_got == b_got,
+            (Self::RateLimited { retry_after: a }, Self::RateLimited { retry_after: b }) => a == b,
+            (Self::CircuitOpen { retry_at: a }, Self::CircuitOpen { retry_at: b }) => a == b,
+            (Self::EmptyQuery, Self::EmptyQuery) => true,
+            (Self::Cancelled, Self::Cancelled) => true,
+            (Self::DeadlineExceeded, Self::DeadlineExceeded) => true,
+            (Self::InvalidBaseUrl(a), Self::InvalidBaseUrl(b)) => a == b,
+            (Self::InvalidHeader(a), Self::InvalidHeader(b)) => a == b,
+            (
+                Self::InvalidEnvVar {
+                    name: a_name,
+                    value: a_value,
+                },
+                Self::InvalidEnvVar {
+                    name: b_name,
+                    value: b_value,
+                },
+            ) => a_name == b_name && a_value == b_value,
+            (Self::NoResults, Self::NoResults) => true,
+            (Self::InvalidQuery { reason: a }, Self::InvalidQuery { reason: b }) => a == b,
+            (Self::InvalidCertificate(a), Self::InvalidCertificate(b)) => a == b,
+            (
+                Self::CertificatePinMismatch { fingerprint: a },
+                Self::CertificatePinMismatch { fingerprint: b },
+            ) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// A validated search query.
+///
+/// A `Query` is trimmed of leading and trailing whitespace, and is never empty.
+/// Because it dereferences to a `str`, it can be passed directly to
+/// [`search`](search):
+///
+/// ```no_run
+/// # use sunxdcc::Query;
+/// let query: Query = "  the hitchhiker's guide to the galaxy ".parse().unwrap();
+/// for result in sunxdcc::search(&query) {
+///     println!("{:?}", result.unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Query(String);
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let query = s.trim();
+        if query.is_empty() {
+            return Err(Error::EmptyQuery);
+        }
+
+        Ok(Self(query.into()))
+    }
+}
+
+impl TryFrom<&str> for Query {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Deref for Query {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Query {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Check that `query` is non-blank, and between `min_len` and `max_len`
+/// characters long once trimmed.
+///
+/// Blank queries produce [`Error::EmptyQuery`], as when parsing a [`Query`].
+#[cfg(feature = "blocking")]
+fn validate_query(query: &str, min_len: usize, max_len: usize) -> Result<(), Error> {
+    let query = query.trim();
+    let len = query.chars().count();
+
+    let reason = if query.is_empty() {
+        return Err(Error::EmptyQuery);
+    } else if len < min_len {
+        format!("query is {len} characters long, but must be at least {min_len}")
+    } else if len > max_len {
+        format!("query is {len} characters long, but must be at most {max_len}")
+    } else {
+        return Ok(());
+    };
+
+    Err(Error::InvalidQuery { reason })
+}
+
+/// Split `query` into its positive part and its excluded terms, i.e. the
+/// trailing run of words with a leading `-`. Excluded terms are lowercased, in
+/// the order given.
+#[cfg(feature = "blocking")]
+fn split_excluded_terms(query: &str) -> (String, Vec<String>) {
+    let mut words: Vec<_> = query.split_whitespace().collect();
+    let mut excluded = vec![];
+    while let Some(term) = words.last().and_then(|word| word.strip_prefix('-')) {
+        if term.is_empty() {
+            break;
+        }
+        excluded.push(term.to_lowercase());
+        words.pop();
+    }
+
+    if excluded.is_empty() {
+        return (query.into(), excluded);
+    }
+
+    excluded.reverse();
+    (words.join(" "), excluded)
+}
+
+/// Replace the characters commonly used as word separators in filenames
+/// (`.` and `_`) with spaces.
+#[cfg(feature = "blocking")]
+fn separate_words(s: &str) -> String {
+    s.replace(['.', '_'], " ")
+}
+
+/// Returns whether `filename` contains none of the `excluded` terms, and all of
+/// the `required` ones once its words are separated, ignoring case.
+#[cfg(feature = "blocking")]
+fn filename_matches(filename: &str, excluded: &[String], required: &[String]) -> bool {
+    let filename = filename.to_lowercase();
+    if excluded.iter().any(|term| filename.contains(term)) {
+        return false;
+    }
+
+    let words = separate_words(&filename);
+    required.iter().all(|term| words.contains(term))
 }
 
 /// Represents the raw results from a single search request's response.
+#[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
 #[derive(Deserialize)]
 struct RawResult {
     network: Vec<String>,
@@ -36,6 +463,7 @@ struct RawResult {
     botrec: Vec<String>,
 }
 
+#[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
 impl RawResult {
     /// Are the contents of this `RawResult` consistent?
     ///
@@ -52,31 +480,61 @@ impl RawResult {
     }
 
     /// Consume this `RawResult`, constructing into `results`.
-    fn consume(self, results: &mut Vec<SearchResult>) -> Result<(), Error> {
+    ///
+    /// If `trim` is set, leading and trailing whitespace is trimmed from every
+    /// field.
+    // The field conversions are identities without the `typed-fields` feature.
+    #[allow(clippy::useless_conversion)]
+    fn consume(mut self, results: &mut Vec<SearchResult>, trim: bool) -> Result<(), Error> {
         if !self.is_consistent() {
             return Err(Error::Malformed("mismatch in adjacent list sizes".into()));
         }
 
+        if trim {
+            for column in [
+                &mut self.network,
+                &mut self.channel,
+                &mut self.bot,
+                &mut self.fsize,
+                &mut self.fname,
+                &mut self.packnum,
+                &mut self.gets,
+                &mut self.botrec,
+            ] {
+                for field in column.iter_mut() {
+                    let trimmed = field.trim();
+                    if trimmed.len() != field.len() {
+                        *field = trimmed.to_string();
+                    }
+                }
+            }
+        }
+
         // Each result is inserted in reverse order, so that we can `pop` them later.
-        for (network, channel, bot, fsize, fname, packnum, gets, botrec) in izip!(
-            self.network.into_iter().rev(),
-            self.channel.into_iter().rev(),
-            self.bot.into_iter().rev(),
-            self.fsize.into_iter().rev(),
-            self.fname.into_iter().rev(),
-            self.packnum.into_iter().rev(),
-            self.gets.into_iter().rev(),
-            self.botrec.into_iter().rev(),
-        ) {
+        let columns = zip(
+            zip(
+                zip(
+                    self.network.into_iter().rev(),
+                    self.channel.into_iter().rev(),
+                ),
+                zip(self.bot.into_iter().rev(), self.fsize.into_iter().rev()),
+            ),
+            zip(
+                zip(self.fname.into_iter().rev(), self.packnum.into_iter().rev()),
+                zip(self.gets.into_iter().rev(), self.botrec.into_iter().rev()),
+            ),
+        );
+
+        for (((network, channel), (bot, fsize)), ((fname, packnum), (gets, botrec))) in columns {
             let botrec = match botrec.as_str() {
                 "Na" => None,
                 _ => Some(botrec),
             };
 
             results.push(SearchResult {
-                network: network,
-                channel: channel,
-                bot: bot,
+                network: network.into(),
+                channel: channel.into(),
+                bot: bot.into(),
                 filesize: fsize,
                 filename: fname,
                 packet_number: packnum,
@@ -89,23 +547,58 @@ impl RawResult {
     }
 }
 
+/// Parse a page's body into its results, in the order that the API returned
+/// them.
+#[cfg(any(feature = "async", feature = "test-util"))]
+pub(crate) fn parse_page(body: &[u8]) -> Result<Vec<SearchResult>, Error> {
+    let mut results = vec![];
+    serde_json::from_slice::<RawResult>(body)
+        .map_err(|e| Error::Malformed(e.to_string()))?
+        .consume(&mut results, false)?;
+
+    // `consume` produces results in reverse order, for popping.
+    results.reverse();
+    Ok(results)
+}
+
 /// Represents a single result from SunXDCC's search API.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchResult {
     /// The IRC network that this result's bot is on.
     ///
     /// This is typically the fully-qualified domain name (e.g. `irc.foo.net`) or
     /// IP address for the record.
+    #[cfg(not(feature = "typed-fields"))]
     pub network: String,
 
+    /// The IRC network that this result's bot is on.
+    ///
+    /// This is typically the fully-qualified domain name (e.g. `irc.foo.net`) or
+    /// IP address for the record.
+    #[cfg(feature = "typed-fields")]
+    pub network: Network,
+
     /// The IRC channel that this result's bot is on.
     ///
     /// This is typically formatted as `"#channelname"`.
+    #[cfg(not(feature = "typed-fields"))]
     pub channel: String,
 
+    /// The IRC channel that this result's bot is on.
+    ///
+    /// This is typically formatted as `"#channelname"`.
+    #[cfg(feature = "typed-fields")]
+    pub channel: Channel,
+
     /// The IRC bot's name.
+    #[cfg(not(feature = "typed-fields"))]
     pub bot: String,
 
+    /// The IRC bot's name.
+    #[cfg(feature = "typed-fields")]
+    pub bot: Bot,
+
     /// The size of the file.
     ///
     /// This is typically formatted as `"[XXXS]"`, e.g. `"[123M]"` for 123MB.
@@ -130,90 +623,3091 @@ pub struct SearchResult {
     pub upload_speed: Option<String>,
 }
 
-/// A stateful iteration container for search results.
-#[derive(Debug)]
-pub struct SearchResults<'search> {
-    /// The client to use for all requests.
-    client: reqwest::blocking::Client,
-    /// The search query.
-    query: &'search str,
-    /// The current result page.
-    current_page: usize,
-    /// The current list of results.
-    current_results: Vec<SearchResult>,
-}
+impl SearchResult {
+    /// Split this result's bracketed filesize into its numeric and unit parts.
+    ///
+    /// For example, `"[123M]"` becomes `("123", "M")` and `"[4.5G]"` becomes
+    /// `("4.5", "G")`. Returns `None` if the filesize isn't bracketed or has
+    /// no numeric part.
+    pub fn filesize_parts(&self) -> Option<(&str, &str)> {
+        let inner = self.filesize.strip_prefix('[')?.strip_suffix(']')?;
+        let split = inner
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(inner.len());
 
-impl<'search> SearchResults<'search> {
-    fn new(query: &'search str) -> Self {
-        // Each query returns a maximum number of 50 results, so reserve at least
-        // that many elements in our `current_results` buffer.
-        Self {
-            client: reqwest::blocking::Client::new(),
-            query: query,
-            current_page: 0,
-            current_results: Vec::with_capacity(50),
+        match inner.split_at(split) {
+            ("", _) => None,
+            parts => Some(parts),
         }
     }
 
-    /// Refresh our internal state, fetching more results from the API if available.
+    /// Parse this result's filesize into a [`FileSize`](FileSize).
     ///
-    /// This function doesn't check whether the current results have been fully consumed;
-    /// callers must take care to fully consume all current results to avoid silently
-    /// skipping results.
-    fn refresh(&mut self) -> Result<(), Error> {
-        self.current_results.clear();
+    /// Returns `None` if the filesize is malformed.
+    pub fn filesize_parsed(&self) -> Option<FileSize> {
+        self.filesize.parse().ok()
+    }
 
-        // Unwrap safety: BASE_URL is a correct URL and our parameters cannot cause an error.
-        #[allow(clippy::unwrap_used)]
-        let url = Url::parse_with_params(
-            BASE_URL,
-            &[
-                ("sterm", self.query),
-                ("page", &self.current_page.to_string()),
-            ],
-        )
-        .unwrap();
+    /// Returns this result's filesize in bytes, or `None` if it is malformed.
+    pub fn filesize_bytes(&self) -> Option<u64> {
+        self.filesize_parsed().map(|size| size.bytes())
+    }
 
-        self.client
-            .get(url)
-            .send()?
-            .json::<RawResult>()?
-            .consume(&mut self.current_results)?;
+    /// Split this result's network into its host and port suffix, if it has one.
+    fn network_parts(&self) -> (&str, Option<&str>) {
+        split_host_port(&self.network)
+    }
 
-        self.current_page += 1;
+    /// Returns this result's network hostname, without any port suffix.
+    ///
+    /// For example, both `"irc.rizon.net"` and `"irc.rizon.net:6667"` have the
+    /// host `"irc.rizon.net"`. Brackets around IPv6 addresses are removed, so
+    /// `"[::1]:6667"` has the host `"::1"`.
+    pub fn network_host(&self) -> &str {
+        self.network_parts().0
+    }
 
-        Ok(())
+    /// Returns this result's network hostname, if it's a valid IP address or
+    /// DNS name.
+    ///
+    /// DNS names are checked syntactically: each dot-separated label must be
+    /// 1 to 63 ASCII letters, digits, or hyphens, not starting or ending with a
+    /// hyphen. This catches garbage values before they reach an IRC client.
+    pub fn network_host_checked(&self) -> Result<&str, Error> {
+        let host = self.network_host();
+        if self.network_parsed().is_ok() || is_dns_name(host) {
+            Ok(host)
+        } else {
+            Err(Error::Malformed(format!(
+                "invalid network host: {}",
+                &*self.network
+            )))
+        }
+    }
+
+    /// Parse this result's network hostname as an IP address.
+    ///
+    /// Returns [`Error::Malformed`](Error::Malformed) if the network isn't an
+    /// IP address; most networks are DNS names instead (see
+    /// [`network_host_checked`](SearchResult::network_host_checked)).
+    pub fn network_parsed(&self) -> Result<IpAddr, Error> {
+        let host = self.network_host();
+        host.parse()
+            .map_err(|_| Error::Malformed(format!("not an IP address: {host}")))
+    }
+
+    /// Returns the channels in this result's channel field, normalized.
+    ///
+    /// Some results list several channels (e.g. `"#chan1 #chan2"`), or omit the
+    /// leading `#`. This splits the field on whitespace and commas, strips IRC
+    /// formatting codes, adds a `#` to names without a channel prefix, and
+    /// lowercases each name. The raw [`channel`](SearchResult::channel) field
+    /// is left untouched.
+    ///
+    /// Names are lowercased with ASCII case-folding. Servers advertising the
+    /// `rfc1459` casemapping additionally treat `[]\~` as the uppercase forms
+    /// of `{}|^`, which isn't applied here.
+    ///
+    /// ```
+    /// # use sunxdcc::{Channel, SearchResult};
+    /// let mut result = SearchResult {
+    ///     network: "irc.foo.net".into(),
+    ///     channel: "#Foo".into(),
+    ///     bot: "foobot".into(),
+    ///     filesize: "[1M]".into(),
+    ///     filename: "foo.txt".into(),
+    ///     packet_number: "#12".into(),
+    ///     download_count: "0x".into(),
+    ///     upload_speed: None,
+    /// };
+    /// assert_eq!(result.channels(), [Channel::from("#foo")]);
+    ///
+    /// result.channel = "#Foo #bar,baz".into();
+    /// assert_eq!(
+    ///     result.channels(),
+    ///     [Channel::from("#foo"), Channel::from("#bar"), Channel::from("#baz")]
+    /// );
+    ///
+    /// result.channel = "\x02\x0304,01foo\x0f".into();
+    /// assert_eq!(result.primary_channel(), Some(Channel::from("#foo")));
+    ///
+    /// result.channel = " ".into();
+    /// assert!(result.channels().is_empty());
+    /// assert_eq!(result.primary_channel(), None);
+    /// ```
+    pub fn channels(&self) -> Vec<Channel> {
+        split_channels(&self.channel)
+    }
+
+    /// Returns the first of this result's [`channels`](SearchResult::channels),
+    /// if any.
+    pub fn primary_channel(&self) -> Option<Channel> {
+        self.channels().into_iter().next()
+    }
+
+    /// Parse this result's network into its host and port.
+    ///
+    /// Networks are bare hosts (`"irc.rizon.net"`), hosts with ports
+    /// (`"irc.rizon.net:6697"`), or IP addresses, including IPv6 addresses with
+    /// or without brackets (`"[::1]:6667"` or `"::1"`). Bare IPv6 addresses
+    /// never have a port.
+    ///
+    /// Returns [`Error::Malformed`](Error::Malformed) if the host is neither
+    /// an IP address nor a valid DNS name (see
+    /// [`network_host_checked`](SearchResult::network_host_checked)), or if
+    /// the port isn't a valid port number.
+    ///
+    /// ```
+    /// # use sunxdcc::SearchResult;
+    /// let mut result = SearchResult {
+    ///     network: "[2001:db8::1]:6697".into(),
+    ///     channel: "#foo".into(),
+    ///     bot: "foobot".into(),
+    ///     filesize: "[1M]".into(),
+    ///     filename: "foo.txt".into(),
+    ///     packet_number: "#12".into(),
+    ///     download_count: "0x".into(),
+    ///     upload_speed: None,
+    /// };
+    ///
+    /// let addr = result.network_addr().unwrap();
+    /// assert_eq!((addr.host, addr.port, addr.is_ip), ("2001:db8::1", Some(6697), true));
+    ///
+    /// result.network = "::1".into();
+    /// let addr = result.network_addr().unwrap();
+    /// assert_eq!((addr.host, addr.port), ("::1", None));
+    ///
+    /// result.network = "irc.foo.net".into();
+    /// let addr = result.network_addr().unwrap();
+    /// assert_eq!((addr.host, addr.port, addr.is_ip), ("irc.foo.net", None, false));
+    ///
+    /// result.network = "irc.foo.net:99999".into();
+    /// assert!(result.network_addr().is_err());
+    ///
+    /// result.network = "not a host!".into();
+    /// assert!(result.network_addr().is_err());
+    /// ```
+    pub fn network_addr(&self) -> Result<NetworkAddr<'_>, Error> {
+        let (host, port) = self.network_parts();
+        let malformed =
+            |what: &str| Error::Malformed(format!("invalid network {what}: {:?}", &*self.network));
+
+        let port = match port {
+            Some(port) => Some(port.parse().map_err(|_| malformed("port"))?),
+            None => None,
+        };
+
+        let is_ip = host.parse::<IpAddr>().is_ok();
+        if !is_ip && !is_dns_name(host) {
+            return Err(malformed("host"));
+        }
+
+        Ok(NetworkAddr {
+            host: host,
+            port: port,
+            is_ip: is_ip,
+        })
+    }
+
+    /// Returns this result's network port.
+    ///
+    /// Networks without a port suffix use the standard IRC port, 6667. Returns
+    /// `None` if the port suffix isn't a valid port number.
+    pub fn network_port(&self) -> Option<u16> {
+        match self.network_parts().1 {
+            Some(port) => port.parse().ok(),
+            None => Some(DEFAULT_IRC_PORT),
+        }
+    }
+
+    /// Parse this result's upload speed into an [`UploadSpeed`](UploadSpeed).
+    ///
+    /// Returns `None` if the upload speed is unknown or malformed.
+    pub fn upload_speed_parsed(&self) -> Option<UploadSpeed> {
+        self.upload_speed.as_deref()?.parse().ok()
+    }
+
+    /// Parse this result's download count, e.g. `"5x"` into `5`.
+    ///
+    /// Returns `None` if the download count is malformed.
+    pub fn download_count_value(&self) -> Option<u64> {
+        self.download_count.strip_suffix('x')?.parse().ok()
+    }
+
+    /// Parse this result's packet number, e.g. `"#123"` into `123`.
+    pub fn packet_number_u64(&self) -> Result<u64, Error> {
+        self.packet_number
+            .strip_prefix('#')
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| {
+                Error::Malformed(format!("invalid packet number: {:?}", self.packet_number))
+            })
+    }
+
+    /// Parse this result's packet number, e.g. `"#123"` into `123`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the packet number isn't in `#NNN` format. This is only
+    /// appropriate for results known to come from a well-formed API response;
+    /// see [`packet_number_u64`](SearchResult::packet_number_u64) for a
+    /// non-panicking variant.
+    pub fn packet_number_raw(&self) -> u64 {
+        match self.packet_number_u64() {
+            Ok(n) => n,
+            #[allow(clippy::panic)]
+            Err(_) => panic!(
+                "packet_number '{}' is not in '#NNN' format",
+                self.packet_number
+            ),
+        }
+    }
+
+    /// Returns whether this result's bot appears to be active.
+    ///
+    /// This is a rough heuristic: a result is considered likely to be available
+    /// if it has been downloaded at least once and its bot reports an upload
+    /// speed. It can be wrong in both directions, since SunXDCC's data is cached
+    /// and dead bots often continue to appear in search results.
+    pub fn is_likely_available(&self) -> bool {
+        self.download_count_value().unwrap_or(0) > 0 && self.upload_speed.is_some()
+    }
+
+    /// Returns whether this result and `other` refer to the same file offer.
+    ///
+    /// An XDCC offer is identified by where it's served from: its network,
+    /// channel, bot, and packet number. Unlike `==`, this ignores fields that
+    /// change between searches, such as the download count and upload speed,
+    /// as well as the filename and filesize, which are properties of the offer
+    /// rather than its identity.
+    pub fn same_file(&self, other: &SearchResult) -> bool {
+        self.network == other.network
+            && self.channel == other.channel
+            && self.bot == other.bot
+            && self.packet_number == other.packet_number
+    }
+
+    /// Returns a comparator that orders results by file size, smallest first,
+    /// for use with [`slice::sort_by`].
+    ///
+    /// Sizes are compared by [`filesize_bytes`](SearchResult::filesize_bytes),
+    /// with malformed sizes ordered before all others.
+    pub fn compare_by_size() -> impl Fn(&SearchResult, &SearchResult) -> cmp::Ordering {
+        |a, b| a.filesize_bytes().cmp(&b.filesize_bytes())
+    }
+
+    /// Returns a comparator that orders results by download count, fewest
+    /// first, for use with [`slice::sort_by`].
+    ///
+    /// Counts are compared by
+    /// [`download_count_value`](SearchResult::download_count_value), with
+    /// malformed counts ordered before all others.
+    pub fn compare_by_downloads() -> impl Fn(&SearchResult, &SearchResult) -> cmp::Ordering {
+        |a, b| a.download_count_value().cmp(&b.download_count_value())
+    }
+
+    /// Returns the header row for [`to_tsv_row`](SearchResult::to_tsv_row),
+    /// without a trailing newline.
+    pub fn tsv_header() -> &'static str {
+        "network\tchannel\tbot\tfilesize\tfilename\tpacket_number\tdownload_count\tupload_speed"
+    }
+
+    /// Format this result as a row of tab-separated values, without a
+    /// trailing newline.
+    ///
+    /// Fields are written in declaration order, matching
+    /// [`tsv_header`](SearchResult::tsv_header). Tabs and line breaks within
+    /// fields are replaced with spaces, and a missing upload speed is written
+    /// as an empty field.
+    ///
+    /// ```
+    /// # use sunxdcc::SearchResult;
+    /// # fn row(result: &SearchResult) {
+    /// println!("{}", SearchResult::tsv_header());
+    /// println!("{}", result.to_tsv_row());
+    /// # }
+    /// ```
+    pub fn to_tsv_row(&self) -> String {
+        [
+            &*self.network,
+            &*self.channel,
+            &*self.bot,
+            &self.filesize,
+            &self.filename,
+            &self.packet_number,
+            &self.download_count,
+            self.upload_speed.as_deref().unwrap_or_default(),
+        ]
+        .map(|field| field.replace(['\t', '\n', '\r'], " "))
+        .join("\t")
     }
 }
 
-impl Iterator for SearchResults<'_> {
-    type Item = Result<SearchResult, Error>;
+/// A criterion for ranking results, for use with
+/// [`SearchResults::best`](SearchResults::best),
+/// [`SearchResults::top_n`](SearchResults::top_n), and
+/// [`SearchResultSet::sort_by`](SearchResultSet::sort_by).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Criterion {
+    /// Rank by download count, as parsed by
+    /// [`SearchResult::download_count_value`].
+    Downloads,
+    /// Rank by the bot's upload speed, as parsed by
+    /// [`SearchResult::upload_speed_parsed`].
+    Speed,
+    /// Rank by file size, as parsed by [`SearchResult::filesize_bytes`].
+    Size,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // If we're just starting or we've exhausted our results, refresh our state.
-        if self.current_page == 0 || self.current_results.is_empty() {
-            if let Err(e) = self.refresh() {
-                return Some(Err(e));
-            }
+impl Criterion {
+    /// Returns the value of this criterion for `result`, or `None` if it's
+    /// unknown or malformed. Higher values rank better.
+    pub fn value(self, result: &SearchResult) -> Option<u64> {
+        match self {
+            Criterion::Downloads => result.download_count_value(),
+            Criterion::Speed => result
+                .upload_speed_parsed()
+                .map(|speed| speed.bytes_per_second()),
+            Criterion::Size => result.filesize_bytes(),
         }
+    }
+}
 
-        // NOTE: This produces results in the correct order, despite the `pop`.
-        // See the implementation of RawResult::consume.
-        Ok(self.current_results.pop()).transpose()
+/// A result's network, parsed into its host and port.
+///
+/// See [`SearchResult::network_addr`](SearchResult::network_addr).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NetworkAddr<'a> {
+    /// The network's host, without brackets if it's an IPv6 address.
+    pub host: &'a str,
+    /// The network's port, if given.
+    pub port: Option<u16>,
+    /// Whether the host is an IP address, rather than a DNS name.
+    pub is_ip: bool,
+}
+
+impl NetworkAddr<'_> {
+    /// Resolve this network's socket addresses, using `default_port` if it
+    /// has no port.
+    ///
+    /// Like [`ToSocketAddrs`], this performs a blocking DNS lookup if the host
+    /// isn't an IP address.
+    pub fn to_socket_addrs_default(
+        &self,
+        default_port: u16,
+    ) -> io::Result<vec::IntoIter<SocketAddr>> {
+        (self.host, self.port.unwrap_or(default_port)).to_socket_addrs()
     }
 }
 
-/// Search SunXDCC for the given `query`.
+/// Parse a certificate in either PEM or DER form.
+#[cfg(feature = "blocking")]
+fn parse_certificate(cert: &[u8]) -> Result<reqwest::Certificate, Error> {
+    let result = match cert.trim_ascii_start().starts_with(b"-----BEGIN") {
+        true => reqwest::Certificate::from_pem(cert),
+        false => reqwest::Certificate::from_der(cert),
+    };
+
+    result.map_err(|e| Error::InvalidCertificate(e.to_string()))
+}
+
+/// Split `network` into its host and port suffix, if it has one.
 ///
-/// The returned `SearchResults` is an [`Iterator`](Iterator) over individual
-/// [`SearchResult`](SearchResult) items.
+/// IPv6 addresses may be bracketed, with an optional port after the closing
+/// bracket; unbracketed IPv6 addresses never have a port.
+fn split_host_port(network: &str) -> (&str, Option<&str>) {
+    if let Some((host, rest)) = network
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+    {
+        return match rest.strip_prefix(':') {
+            Some(port) => (host, Some(port)),
+            None if rest.is_empty() => (host, None),
+            None => (network, None),
+        };
+    }
+
+    match network.rsplit_once(':') {
+        Some((host, _)) if host.contains(':') => (network, None),
+        Some((host, port)) => (host, Some(port)),
+        None => (network, None),
+    }
+}
+
+/// Returns whether `host` is a syntactically valid DNS name.
+fn is_dns_name(host: &str) -> bool {
+    let host = host.strip_suffix('.').unwrap_or(host);
+
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// Results are ordered by filename, case-insensitively.
 ///
-/// ```no_run
-/// # use sunxdcc;
-/// for result in sunxdcc::search("the hitchhiker's guide to the galaxy") {
-///     println!("{:?}", result.unwrap());
-/// }
+/// This ordering is arbitrary, but convenient for presentation. To remain
+/// consistent with `==`, results with the same filename are then ordered by
+/// the exact filename, followed by each of their other fields.
+impl Ord for SearchResult {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        fn folded(s: &str) -> impl Iterator<Item = char> + '_ {
+            s.chars().flat_map(char::to_lowercase)
+        }
+
+        folded(&self.filename)
+            .cmp(folded(&other.filename))
+            .then_with(|| {
+                (
+                    &self.filename,
+                    &self.network,
+                    &self.channel,
+                    &self.bot,
+                    &self.packet_number,
+                    &self.filesize,
+                    &self.download_count,
+                    &self.upload_speed,
+                )
+                    .cmp(&(
+                        &other.filename,
+                        &other.network,
+                        &other.channel,
+                        &other.bot,
+                        &other.packet_number,
+                        &other.filesize,
+                        &other.download_count,
+                        &other.upload_speed,
+                    ))
+            })
+    }
+}
+
+impl PartialOrd for SearchResult {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Results are displayed on a single line, with the XDCC command that
+/// requests the file:
+///
+/// ```text
+/// [1.2G] file.mkv - /msg foobot xdcc send #12 (#foo on irc.foo.net)
 /// ```
-pub fn search(query: &str) -> SearchResults {
-    SearchResults::new(query)
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} - /msg {} xdcc send {} ({} on {})",
+            self.filesize, self.filename, self.bot, self.packet_number, self.channel, self.network
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl SearchResult {
+    /// Serialize this result as a compact JSON string.
+    pub fn try_to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Serialize this result as a compact JSON string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if serialization fails, which cannot happen for a `SearchResult`.
+    /// See [`try_to_json_string`](SearchResult::try_to_json_string) for a
+    /// non-panicking variant.
+    pub fn to_json_string(&self) -> String {
+        // Expect safety: every field is a string or an optional string, which
+        // always serialize successfully.
+        #[allow(clippy::expect_used)]
+        self.try_to_json_string()
+            .expect("SearchResult serialization is infallible")
+    }
+
+    /// Serialize this result as a pretty-printed JSON string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if serialization fails, which cannot happen for a `SearchResult`.
+    pub fn to_json_string_pretty(&self) -> String {
+        // Expect safety: see `to_json_string`.
+        #[allow(clippy::expect_used)]
+        serde_json::to_string_pretty(self).expect("SearchResult serialization is infallible")
+    }
+}
+
+/// The state of a circuit breaker.
+#[cfg(feature = "blocking")]
+#[derive(Debug, Default)]
+struct CircuitState {
+    /// The number of consecutive failed page fetches.
+    failures: u32,
+    /// If the breaker is open, the time at which it allows a probe request.
+    open_until: Option<Instant>,
+}
+
+/// A circuit breaker, which stops page fetches for a cool-down period after
+/// too many consecutive failures.
+///
+/// A breaker is shared by every search made through the same
+/// [`SearchClient`](SearchClient).
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+struct CircuitBreaker {
+    /// The number of consecutive failures that trips the breaker.
+    threshold: u32,
+    /// How long the breaker stays open once tripped.
+    cooldown: Duration,
+    /// The breaker's current state.
+    state: Mutex<CircuitState>,
+}
+
+#[cfg(feature = "blocking")]
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold,
+            cooldown: cooldown,
+            state: Mutex::new(CircuitState::default()),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CircuitState> {
+        // A poisoned lock can only mean that another thread panicked mid-update,
+        // which leaves the state no less consistent than it was.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Check whether a request may be made, failing fast if the breaker is open.
+    ///
+    /// Once the cool-down has elapsed, exactly one probe request is allowed
+    /// through; other callers continue to fail fast until it completes.
+    fn check(&self) -> Result<(), Error> {
+        let mut state = self.state();
+        let now = Instant::now();
+
+        match state.open_until {
+            Some(retry_at) if now < retry_at => Err(Error::CircuitOpen { retry_at: retry_at }),
+            Some(_) => {
+                state.open_until = Some(now + self.cooldown);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Record the outcome of a request, tripping the breaker if necessary.
+    fn record(&self, success: bool) {
+        let mut state = self.state();
+
+        if success {
+            *state = CircuitState::default();
+        } else {
+            state.failures = state.failures.saturating_add(1);
+            if state.failures >= self.threshold {
+                state.open_until = Some(Instant::now() + self.cooldown);
+            }
+        }
+    }
+}
+
+/// A builder for a configured [`SearchClient`](SearchClient).
+///
+/// ```no_run
+/// # use sunxdcc::SearchBuilder;
+/// let client = SearchBuilder::new().build().unwrap();
+/// for result in client.search("the hitchhiker's guide to the galaxy") {
+///     println!("{:?}", result.unwrap());
+/// }
+/// ```
+#[cfg(feature = "blocking")]
+#[derive(Debug, Default)]
+pub struct SearchBuilder {
+    /// Whether to accept invalid TLS certificates.
+    danger_accept_invalid_certs: bool,
+    /// The circuit breaker's threshold and cool-down, if enabled.
+    circuit_breaker: Option<(u32, Duration)>,
+    /// The initial capacity of each search's result buffer, if overridden.
+    page_capacity_hint: Option<usize>,
+    /// Whether to assume that the server speaks HTTP/2.
+    http2_prior_knowledge: bool,
+    /// The client's redirect policy, if overridden.
+    redirect: Option<reqwest::redirect::Policy>,
+    /// The timeout for each request, if overridden.
+    timeout: Option<Duration>,
+    /// The proxy to route requests through, if any.
+    proxy: Option<String>,
+    /// The maximum duration of each search, if any.
+    deadline: Option<Duration>,
+    /// The API endpoint to query, if overridden.
+    base_url: Option<String>,
+    /// How spaces in queries are encoded.
+    query_encoding: QueryEncoding,
+    /// The page cache's configuration, if enabled.
+    cache: Option<CacheConfig>,
+    /// The maximum number of bytes to read from a single response, if overridden.
+    max_response_bytes: Option<usize>,
+    /// Whether a search with no results produces an error.
+    error_on_empty: bool,
+    /// The callback for successful page fetches, if any.
+    on_page: Option<PageHook>,
+    /// The callback for failed page fetches, if any.
+    on_page_error: Option<PageErrorHook>,
+    /// The minimum and maximum query lengths, if overridden.
+    query_len: Option<(usize, usize)>,
+    /// The `Accept` header to send, if overridden.
+    accept: Option<String>,
+    /// The number of times to re-fetch an empty page.
+    empty_page_tolerance: u32,
+    /// The callback for raw response bodies, if any.
+    on_raw_response: Option<RawResponseHook>,
+    /// Whether to normalize queries before sending them.
+    normalize_query: bool,
+    /// Terms whose presence in a filename excludes a result, lowercased.
+    excluded_terms: Vec<String>,
+    /// Whether words with a leading `-` in queries are excluded terms.
+    parse_excluded_terms: bool,
+    /// Whether results must contain every term of the query.
+    require_all_terms: bool,
+    /// The maximum number of pages fetched by each search, if overridden.
+    max_pages: Option<usize>,
+    /// Whether to trim whitespace from every field of each result.
+    trim_fields: bool,
+    /// Additional root certificates to trust, in PEM or DER form.
+    root_certificates: Vec<Vec<u8>>,
+    /// The SHA-256 fingerprints of the certificates to pin, as given.
+    #[cfg(feature = "pinning")]
+    pinned_certificates: Vec<String>,
+    /// Whether to strip diacritics from queries before sending them.
+    #[cfg(feature = "unicode")]
+    strip_diacritics: bool,
+    /// The on-disk cache's directory and TTL, if enabled.
+    #[cfg(feature = "disk-cache")]
+    disk_cache: Option<(PathBuf, Duration)>,
+    /// Whether to bypass existing on-disk cache entries.
+    #[cfg(feature = "disk-cache")]
+    refresh_disk_cache: bool,
+}
+
+#[cfg(feature = "blocking")]
+impl SearchBuilder {
+    /// Create a new `SearchBuilder` with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new `SearchBuilder`, configured from the environment.
+    ///
+    /// The following environment variables are consulted, and are ignored
+    /// when unset or empty:
+    ///
+    /// * `HTTPS_PROXY` (or `https_proxy`): a proxy URL to route requests
+    ///   through; see [`proxy`](SearchBuilder::proxy).
+    /// * `SUNXDCC_TIMEOUT_SECS`: a whole number of seconds to use as the
+    ///   timeout for each request; see [`timeout`](SearchBuilder::timeout).
+    ///
+    /// Any other configuration is left at its default. Returns
+    /// [`Error::InvalidEnvVar`](Error::InvalidEnvVar) if a variable is set to
+    /// an invalid value; an invalid proxy URL is instead reported by
+    /// [`build`](SearchBuilder::build).
+    pub fn from_env() -> Result<Self, Error> {
+        let mut builder = Self::new();
+
+        let proxy = match env_var("HTTPS_PROXY")? {
+            Some(proxy) => Some(proxy),
+            None => env_var("https_proxy")?,
+        };
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(&proxy);
+        }
+
+        const TIMEOUT_VAR: &str = "SUNXDCC_TIMEOUT_SECS";
+        if let Some(timeout) = env_var(TIMEOUT_VAR)? {
+            let secs = timeout.trim().parse().map_err(|_| Error::InvalidEnvVar {
+                name: TIMEOUT_VAR,
+                value: timeout.clone(),
+            })?;
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+
+        Ok(builder)
+    }
+
+    /// Control whether the client accepts invalid TLS certificates.
+    ///
+    /// # Warning
+    ///
+    /// **Never enable this in production.** Accepting invalid certificates means
+    /// that *any* certificate is trusted, including expired, self-signed, and
+    /// attacker-controlled ones. This option exists solely for integration
+    /// testing against local mock servers.
+    ///
+    /// Certificate verification is enabled by default.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Trust an additional root certificate, given in PEM or DER form.
+    ///
+    /// This is useful when requests pass through a TLS-terminating gateway
+    /// with a private CA. The system's root certificates are still trusted.
+    /// An invalid certificate produces an
+    /// [`Error::InvalidCertificate`](Error::InvalidCertificate) from
+    /// [`build`](SearchBuilder::build).
+    ///
+    /// This may be called multiple times, adding to the trusted certificates.
+    ///
+    /// ```
+    /// # use sunxdcc::{Error, SearchClient};
+    /// let ca = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/test-ca.pem"));
+    /// assert!(SearchClient::builder().add_root_certificate(ca).build().is_ok());
+    ///
+    /// let garbage = SearchClient::builder()
+    ///     .add_root_certificate(b"not a certificate")
+    ///     .build();
+    /// assert!(matches!(garbage, Err(Error::InvalidCertificate(_))));
+    /// ```
+    pub fn add_root_certificate(mut self, cert: impl AsRef<[u8]>) -> Self {
+        self.root_certificates.push(cert.as_ref().to_vec());
+        self
+    }
+
+    /// Only accept responses from servers whose certificate has the given
+    /// SHA-256 fingerprint.
+    ///
+    /// The fingerprint is given in hex, optionally separated by colons, as
+    /// printed by e.g. `openssl x509 -noout -fingerprint -sha256`. Once any
+    /// fingerprint is pinned, responses are only accepted from servers whose
+    /// certificate matches one of them; others produce an
+    /// [`Error::CertificatePinMismatch`](Error::CertificatePinMismatch). An
+    /// invalid fingerprint produces an
+    /// [`Error::InvalidCertificate`](Error::InvalidCertificate) from
+    /// [`build`](SearchBuilder::build).
+    ///
+    /// This is post-hoc response validation rather than certificate pinning in
+    /// the usual sense: the fingerprint is checked once each response arrives,
+    /// not during the TLS handshake. A request to a mismatched server, including
+    /// its query, is therefore still sent; only its response is discarded.
+    /// Pages served from the [page cache](SearchBuilder::cache) or the disk
+    /// cache make no request at all, so they aren't checked.
+    ///
+    /// The check is made in addition to the usual certificate validation, so
+    /// a self-signed certificate must also be trusted via
+    /// [`add_root_certificate`](SearchBuilder::add_root_certificate).
+    ///
+    /// This may be called multiple times, e.g. to allow for certificate
+    /// rotation. It requires the `pinning` feature.
+    ///
+    /// ```
+    /// # use sunxdcc::{Error, SearchClient};
+    /// let fingerprint = "AD:11:B7:23:F3:7C:D6:CB:61:9D:58:98:1D:1B:4C:ED:\
+    ///                    40:92:10:5D:9C:7B:4F:5C:BC:3C:56:97:98:F1:32:D3";
+    /// assert!(SearchClient::builder().pin_certificate(fingerprint).build().is_ok());
+    ///
+    /// let truncated = SearchClient::builder().pin_certificate("AD:11:B7").build();
+    /// assert!(matches!(truncated, Err(Error::InvalidCertificate(_))));
+    /// ```
+    #[cfg(feature = "pinning")]
+    pub fn pin_certificate(mut self, sha256_fingerprint: impl Into<String>) -> Self {
+        self.pinned_certificates.push(sha256_fingerprint.into());
+        self
+    }
+
+    /// Enable a circuit breaker that stops fetching pages after `threshold`
+    /// consecutive failures.
+    ///
+    /// Once tripped, searches fail fast with
+    /// [`Error::CircuitOpen`](Error::CircuitOpen) without touching the network
+    /// until `cooldown` has elapsed, after which a single probe request is
+    /// allowed. A successful probe closes the breaker; a failed one re-opens it
+    /// for another `cooldown`.
+    ///
+    /// The breaker's state is shared by all searches made through the built
+    /// client, making it suitable for multi-query jobs.
+    pub fn circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker = Some((threshold, cooldown));
+        self
+    }
+
+    /// Set the initial capacity of each search's internal result buffer.
+    ///
+    /// This is only a memory-tuning hint: it doesn't limit the number of results
+    /// returned, and the buffer grows as needed. The default is 50, which is
+    /// the maximum number of results in a single page.
+    pub fn page_capacity_hint(mut self, capacity: usize) -> Self {
+        self.page_capacity_hint = Some(capacity);
+        self
+    }
+
+    /// Set the initial capacity of each search's internal result buffer.
+    ///
+    /// This is equivalent to [`page_capacity_hint`](Self::page_capacity_hint);
+    /// smaller capacities are useful in memory-constrained environments, or
+    /// when only a handful of results are expected.
+    pub fn page_capacity(self, capacity: usize) -> Self {
+        self.page_capacity_hint(capacity)
+    }
+
+    /// Set the client's redirect policy.
+    ///
+    /// The default is reqwest's default policy, which follows up to 10 redirects.
+    /// Limiting or disabling redirects is useful when a misconfigured mirror
+    /// redirects to an HTML page, which would otherwise surface as a confusing
+    /// [`Error::Malformed`](Error::Malformed).
+    ///
+    /// ```no_run
+    /// # use sunxdcc::SearchBuilder;
+    /// let client = SearchBuilder::new()
+    ///     .redirect(reqwest::redirect::Policy::none())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn redirect(mut self, policy: reqwest::redirect::Policy) -> Self {
+        self.redirect = Some(policy);
+        self
+    }
+
+    /// Set the timeout for each request, from connecting until the response
+    /// body has been read.
+    ///
+    /// This applies to each page fetch separately; see
+    /// [`deadline`](SearchBuilder::deadline) for a limit on an entire search.
+    /// The default is 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Route all requests through the proxy at `url`, e.g.
+    /// `http://proxy.example.com:8080` or `socks5://localhost:1080`.
+    ///
+    /// The URL is validated by [`build`](SearchBuilder::build). By default,
+    /// the system's proxy configuration is used.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Control whether the client speaks HTTP/2 without first negotiating it.
+    ///
+    /// By default, HTTP/2 is only used when negotiated via TLS ALPN. With prior
+    /// knowledge, the client uses HTTP/2 unconditionally, which avoids the
+    /// negotiation but causes every request to fail if the server (or any proxy
+    /// in between) doesn't support HTTP/2.
+    pub fn http2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.http2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Set an overall deadline for each search, measured from its creation.
+    ///
+    /// Once the deadline has passed, a search stops fetching new pages and ends
+    /// after yielding any results it has already buffered;
+    /// [`SearchResults::was_truncated`](SearchResults::was_truncated) reports
+    /// whether this happened. The deadline also bounds each request, which is
+    /// abandoned if it's still in flight when the deadline passes, and cuts
+    /// short any wait before retrying a rate-limited request.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Override the API endpoint that searches are sent to, e.g. to use a
+    /// mirror or a local mock server.
+    ///
+    /// The URL is used verbatim, including its path and any query parameters
+    /// it already has; only the search's own parameters are appended. It may
+    /// be given as a string or a [`Url`](Url), and must be an absolute `http`
+    /// or `https` URL; it is validated by [`build`](SearchBuilder::build). The
+    /// default is SunXDCC's own endpoint, `https://sunxdcc.com/deliver.php`.
+    ///
+    /// ```
+    /// # use std::io::{BufRead, BufReader, Write};
+    /// # use std::net::TcpListener;
+    /// # use sunxdcc::SearchClient;
+    /// # let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// # let port = listener.local_addr().unwrap().port();
+    /// # let server = std::thread::spawn(move || {
+    /// #     let (mut stream, _) = listener.accept().unwrap();
+    /// #     let mut request_line = String::new();
+    /// #     BufReader::new(&stream).read_line(&mut request_line).unwrap();
+    /// #     let body = br#"{"network":[],"channel":[],"bot":[],"fsize":[],"fname":[],"packnum":[],"gets":[],"botrec":[]}"#;
+    /// #     write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+    /// #     stream.write_all(body).unwrap();
+    /// #     request_line
+    /// # });
+    /// let mirror = format!("http://127.0.0.1:{port}/api/v2/search?key=abc");
+    /// let client = SearchClient::builder().base_url(&mirror).build().unwrap();
+    /// assert_eq!(client.search("foo").count(), 0);
+    /// # let request_line = server.join().unwrap();
+    ///
+    /// // The server saw the mirror's path and parameters, followed by the search's.
+    /// assert_eq!(
+    ///     request_line.trim_end(),
+    ///     "GET /api/v2/search?key=abc&sterm=foo&page=0 HTTP/1.1"
+    /// );
+    /// ```
+    pub fn base_url(mut self, url: impl AsRef<str>) -> Self {
+        self.base_url = Some(url.as_ref().into());
+        self
+    }
+
+    /// Set how spaces in search queries are encoded in request URLs.
+    ///
+    /// The default is [`QueryEncoding::Plus`](QueryEncoding::Plus).
+    pub fn query_encoding(mut self, encoding: QueryEncoding) -> Self {
+        self.query_encoding = encoding;
+        self
+    }
+
+    /// Enable an in-memory cache of fetched pages.
+    ///
+    /// Pages are cached by query and page number, and reused for up to
+    /// `config.ttl`; at most `config.capacity` pages are kept, evicting the
+    /// least recently used page when full. The cache is shared by all searches
+    /// made through the built client, and can be inspected via
+    /// [`SearchClient::cache`](SearchClient::cache). Entries only expire with
+    /// their TTL, so they survive [`SearchResults::reset`](SearchResults::reset).
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Set the maximum number of bytes to read from a single response.
+    ///
+    /// Responses that exceed this limit produce an
+    /// [`Error::ResponseTooLarge`](Error::ResponseTooLarge) rather than being
+    /// buffered in full, which protects against misbehaving or untrusted
+    /// mirrors. The default is 4 MiB, which is far larger than any real page.
+    pub fn max_response_bytes(mut self, limit: usize) -> Self {
+        self.max_response_bytes = Some(limit);
+        self
+    }
+
+    /// Control whether a search with no results produces an error.
+    ///
+    /// When enabled, a search whose first page is empty yields a single
+    /// [`Error::NoResults`](Error::NoResults) before ending, rather than
+    /// simply ending. The default is disabled.
+    pub fn error_on_empty(mut self, error_on_empty: bool) -> Self {
+        self.error_on_empty = error_on_empty;
+        self
+    }
+
+    /// Set the allowed length of search queries, in characters.
+    ///
+    /// Queries are trimmed before being measured. A search for a query whose
+    /// length is outside of `min..=max` yields
+    /// [`Error::InvalidQuery`](Error::InvalidQuery) without making any
+    /// requests, just as a blank query yields
+    /// [`Error::EmptyQuery`](Error::EmptyQuery). The default is 2 to 256
+    /// characters.
+    pub fn query_length(mut self, min: usize, max: usize) -> Self {
+        self.query_len = Some((min, max));
+        self
+    }
+
+    /// Re-fetch an empty page up to `retries` times, with a short delay, before
+    /// concluding that a search has ended.
+    ///
+    /// SunXDCC occasionally returns an empty page mid-search when under load,
+    /// which would otherwise end the search early. Since the final page of
+    /// every search is empty, enabling this delays the end of each complete
+    /// search by about half a second per retry. The default is 0.
+    pub fn empty_page_tolerance(mut self, retries: u32) -> Self {
+        self.empty_page_tolerance = retries;
+        self
+    }
+
+    /// Set a callback to be invoked with the raw body of each page, before it's
+    /// parsed.
+    ///
+    /// This is intended for debugging, e.g. for saving the payload that caused
+    /// an [`Error::Malformed`](Error::Malformed). Pages served from the cache
+    /// are passed to the callback too.
+    pub fn on_raw_response(mut self, callback: impl Fn(&[u8]) + Send + Sync + 'static) -> Self {
+        self.on_raw_response = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Exclude results whose filenames contain any of the given `terms`,
+    /// ignoring case.
+    ///
+    /// SunXDCC has no support for excluding terms, so this is done
+    /// client-side: excluded results are dropped as pages are fetched, and
+    /// don't count towards any limits. See also
+    /// [`parse_excluded_terms`](SearchBuilder::parse_excluded_terms).
+    ///
+    /// This may be called multiple times, adding to the excluded terms.
+    pub fn exclude_terms<S: AsRef<str>>(mut self, terms: &[S]) -> Self {
+        self.excluded_terms
+            .extend(terms.iter().map(|term| term.as_ref().to_lowercase()));
+        self
+    }
+
+    /// Control whether trailing words in queries with a leading `-` are
+    /// treated as excluded terms.
+    ///
+    /// When enabled, e.g. the query `"spider-man -720p -hdtv"` searches for
+    /// `spider-man` and excludes results containing `720p` or `hdtv`, as with
+    /// [`exclude_terms`](SearchBuilder::exclude_terms). Only the trailing run
+    /// of such words is parsed, so a query like `"-ology studies"` is searched
+    /// for as-is. The default is disabled, sending queries unchanged.
+    pub fn parse_excluded_terms(mut self, parse: bool) -> Self {
+        self.parse_excluded_terms = parse;
+        self
+    }
+
+    /// Set the maximum number of pages fetched by each search.
+    ///
+    /// Once this many pages have been fetched, the search ends even if more
+    /// results are available, and
+    /// [`SearchResults::was_truncated`](SearchResults::was_truncated) reports
+    /// `true`. This guards against fetching thousands of pages for very common
+    /// queries. The default is 100 pages, or 5,000 results.
+    ///
+    /// The default doesn't apply to methods that consume an entire search to
+    /// produce a single answer, such as [`count`](count),
+    /// [`SearchResults::best`], or [`SearchResults::collect_all`]: these see
+    /// every result unless a limit is set here explicitly.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Control whether results must contain every word of the query in their
+    /// filenames, ignoring case.
+    ///
+    /// SunXDCC's matching is loose, and often returns results that only
+    /// contain some of a query's words. When enabled, such results are dropped
+    /// client-side as pages are fetched, as with
+    /// [`exclude_terms`](SearchBuilder::exclude_terms). For this comparison,
+    /// `.` and `_` count as spaces, so e.g. the query `ubuntu 22.04` matches
+    /// `Ubuntu_22.04.iso` but not `ubuntu-20.04.iso`. The default is disabled.
+    pub fn require_all_terms(mut self, require: bool) -> Self {
+        self.require_all_terms = require;
+        self
+    }
+
+    /// Control whether leading and trailing whitespace is trimmed from every
+    /// field of each result.
+    ///
+    /// SunXDCC occasionally returns fields with stray whitespace, which breaks
+    /// exact comparisons. The default is disabled, leaving fields exactly as
+    /// returned.
+    pub fn trim_fields(mut self, trim: bool) -> Self {
+        self.trim_fields = trim;
+        self
+    }
+
+    /// Control whether queries are normalized before being sent.
+    ///
+    /// Normalization lowercases the query, and with the `unicode` feature also
+    /// applies Unicode NFC normalization; see
+    /// [`normalize_query`](normalize_query). Whitespace is trimmed and
+    /// collapsed regardless. The original query remains available via
+    /// [`SearchResults::query`](SearchResults::query). The default is
+    /// disabled.
+    pub fn normalize_query(mut self, normalize: bool) -> Self {
+        self.normalize_query = normalize;
+        self
+    }
+
+    /// Control whether diacritics are stripped from queries before they're
+    /// sent, e.g. so that `Amélie` also matches `Amelie`.
+    ///
+    /// This is applied after [`normalize_query`](SearchBuilder::normalize_query),
+    /// if both are enabled. The default is disabled.
+    #[cfg(feature = "unicode")]
+    pub fn strip_diacritics(mut self, strip: bool) -> Self {
+        self.strip_diacritics = strip;
+        self
+    }
+
+    /// Set the `Accept` header sent with each API request.
+    ///
+    /// The default is `application/json`. An invalid header value causes
+    /// [`build`](SearchBuilder::build) to fail with
+    /// [`Error::InvalidHeader`](Error::InvalidHeader).
+    pub fn accept(mut self, accept: impl Into<String>) -> Self {
+        self.accept = Some(accept.into());
+        self
+    }
+
+    /// Set a callback to be invoked after each page is fetched, e.g. to
+    /// report progress.
+    ///
+    /// The callback runs synchronously, on the thread iterating the search.
+    /// Failed fetches don't invoke it; see
+    /// [`on_page_error`](SearchBuilder::on_page_error).
+    ///
+    /// ```no_run
+    /// # use sunxdcc::SearchBuilder;
+    /// let client = SearchBuilder::new()
+    ///     .on_page(|info| {
+    ///         eprintln!("fetched page {} ({} results so far)", info.page, info.total_results)
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn on_page(mut self, callback: impl Fn(&PageInfo) + Send + Sync + 'static) -> Self {
+        self.on_page = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Set a callback to be invoked after each failed page fetch, with the
+    /// index of the page and the error.
+    ///
+    /// Like [`on_page`](SearchBuilder::on_page), the callback runs
+    /// synchronously. The error is also yielded by the search as usual.
+    pub fn on_page_error(
+        mut self,
+        callback: impl Fn(usize, &Error) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_page_error = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Enable an on-disk cache of fetched pages in the given directory.
+    ///
+    /// Pages are cached by query and page number, and served from disk for up
+    /// to `ttl` after being fetched. Missing, expired, or corrupt entries
+    /// (e.g. truncated files) are transparently re-fetched from the network.
+    ///
+    /// This requires the `disk-cache` feature.
+    #[cfg(feature = "disk-cache")]
+    pub fn disk_cache(mut self, dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        self.disk_cache = Some((dir.into(), ttl));
+        self
+    }
+
+    /// Control whether existing on-disk cache entries are ignored.
+    ///
+    /// When enabled, every page is fetched from the network and its cache
+    /// entry is replaced. This has no effect unless
+    /// [`disk_cache`](SearchBuilder::disk_cache) is also configured.
+    ///
+    /// This requires the `disk-cache` feature.
+    #[cfg(feature = "disk-cache")]
+    pub fn refresh_disk_cache(mut self, refresh: bool) -> Self {
+        self.refresh_disk_cache = refresh;
+        self
+    }
+
+    /// Build a [`SearchClient`](SearchClient) from this configuration.
+    pub fn build(self) -> Result<SearchClient, Error> {
+        let base_url = match &self.base_url {
+            Some(url) => {
+                let url = Url::parse(url).map_err(|e| Error::InvalidBaseUrl(e.to_string()))?;
+                if !matches!(url.scheme(), "http" | "https") {
+                    return Err(Error::InvalidBaseUrl(format!(
+                        "unsupported scheme: {}",
+                        url.scheme()
+                    )));
+                }
+                url
+            }
+            None => default_base_url(),
+        };
+
+        let accept = self.accept.as_deref().unwrap_or(DEFAULT_ACCEPT);
+        let accept = HeaderValue::from_str(accept)
+            .map_err(|_| Error::InvalidHeader(format!("Accept: {accept:?}")))?;
+
+        let mut builder = reqwest::blocking::Client::builder()
+            .danger_accept_invalid_certs(self.danger_accept_invalid_certs)
+            .default_headers(HeaderMap::from_iter([(ACCEPT, accept)]));
+
+        if let Some(policy) = self.redirect {
+            builder = builder.redirect(policy);
+        }
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        // Persist cookies across page requests, so that any session state set
+        // by the API on one page carries over to the next.
+        #[cfg(feature = "cookies")]
+        {
+            builder = builder.cookie_store(true);
+        }
+
+        for cert in &self.root_certificates {
+            builder = builder.add_root_certificate(parse_certificate(cert)?);
+        }
+
+        #[cfg(feature = "pinning")]
+        let pins = self
+            .pinned_certificates
+            .iter()
+            .map(|pin| transport::parse_fingerprint(pin))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The peer's certificate is only exposed to us with TLS info enabled.
+        #[cfg(feature = "pinning")]
+        if !pins.is_empty() {
+            builder = builder.tls_info(true);
+        }
+
+        let client = builder.build()?;
+
+        Ok(SearchClient {
+            client: client,
+            circuit_breaker: self
+                .circuit_breaker
+                .map(|(threshold, cooldown)| Arc::new(CircuitBreaker::new(threshold, cooldown))),
+            page_capacity: self.page_capacity_hint.unwrap_or(PAGE_SIZE),
+            deadline: self.deadline,
+            request_timeout: self.timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT),
+            base_url: base_url,
+            query_encoding: self.query_encoding,
+            cache: self.cache.map(|config| Arc::new(PageCache::new(config))),
+            max_response_bytes: self
+                .max_response_bytes
+                .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES),
+            error_on_empty: self.error_on_empty,
+            on_page: self.on_page,
+            on_page_error: self.on_page_error,
+            query_options: QueryOptions {
+                len: self
+                    .query_len
+                    .unwrap_or((DEFAULT_MIN_QUERY_LEN, DEFAULT_MAX_QUERY_LEN)),
+                normalize: self.normalize_query,
+                #[cfg(feature = "unicode")]
+                strip_diacritics: self.strip_diacritics,
+                excluded_terms: self.excluded_terms,
+                parse_excluded_terms: self.parse_excluded_terms,
+                require_all_terms: self.require_all_terms,
+            },
+            empty_page_tolerance: self.empty_page_tolerance,
+            on_raw_response: self.on_raw_response,
+            max_pages: self.max_pages,
+            trim_fields: self.trim_fields,
+            #[cfg(feature = "pinning")]
+            pins: pins,
+            #[cfg(feature = "disk-cache")]
+            disk_cache: self.disk_cache,
+            #[cfg(feature = "disk-cache")]
+            refresh_disk_cache: self.refresh_disk_cache,
+        })
+    }
+}
+
+/// A reusable, configured client for SunXDCC searches.
+///
+/// Use a [`SearchBuilder`](SearchBuilder) to construct one.
+#[cfg(feature = "blocking")]
+#[derive(Clone)]
+pub struct SearchClient {
+    /// The underlying HTTP client.
+    client: reqwest::blocking::Client,
+    /// The circuit breaker shared by this client's searches, if any.
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// The initial capacity of each search's result buffer.
+    page_capacity: usize,
+    /// The maximum duration of each search, if any.
+    deadline: Option<Duration>,
+    /// The timeout for each request.
+    request_timeout: Duration,
+    /// The API endpoint to query.
+    base_url: Url,
+    /// How spaces in queries are encoded.
+    query_encoding: QueryEncoding,
+    /// The page cache shared by this client's searches, if any.
+    cache: Option<Arc<PageCache>>,
+    /// The maximum number of bytes to read from a single response.
+    max_response_bytes: usize,
+    /// Whether a search with no results produces an error.
+    error_on_empty: bool,
+    /// The callback for successful page fetches, if any.
+    on_page: Option<PageHook>,
+    /// The callback for failed page fetches, if any.
+    on_page_error: Option<PageErrorHook>,
+    /// How queries are validated and prepared.
+    query_options: QueryOptions,
+    /// The number of times to re-fetch an empty page.
+    empty_page_tolerance: u32,
+    /// The callback for raw response bodies, if any.
+    on_raw_response: Option<RawResponseHook>,
+    /// The maximum number of pages fetched by each search, if set explicitly.
+    max_pages: Option<usize>,
+    /// Whether to trim whitespace from every field of each result.
+    trim_fields: bool,
+    /// The SHA-256 fingerprints of the pinned certificates, if any.
+    #[cfg(feature = "pinning")]
+    pins: Vec<[u8; 32]>,
+    /// The on-disk cache's directory and TTL, if enabled.
+    #[cfg(feature = "disk-cache")]
+    disk_cache: Option<(PathBuf, Duration)>,
+    /// Whether to bypass existing on-disk cache entries.
+    #[cfg(feature = "disk-cache")]
+    refresh_disk_cache: bool,
+}
+
+#[cfg(feature = "blocking")]
+impl fmt::Debug for SearchClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The HTTP client's own `Debug` output is an implementation detail,
+        // and is too noisy to be useful.
+        f.debug_struct("SearchClient")
+            .field("base_url", &self.base_url.as_str())
+            .field("query_encoding", &self.query_encoding)
+            .field("deadline", &self.deadline)
+            .field("circuit_breaker", &self.circuit_breaker.is_some())
+            .field("cache", &self.cache.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl SearchClient {
+    /// Returns the transport for a new search.
+    fn transport(&self) -> Box<dyn Transport> {
+        let transport: Box<dyn Transport> = Box::new(self.client.clone());
+
+        #[cfg(feature = "pinning")]
+        let transport: Box<dyn Transport> = match self.pins.is_empty() {
+            true => transport,
+            false => Box::new(transport::PinnedTransport {
+                client: self.client.clone(),
+                pins: self.pins.clone(),
+            }),
+        };
+
+        #[cfg(feature = "disk-cache")]
+        if let Some((dir, ttl)) = &self.disk_cache {
+            return Box::new(disk_cache::DiskCacheTransport {
+                inner: transport,
+                dir: dir.clone(),
+                ttl: *ttl,
+                refresh: self.refresh_disk_cache,
+            });
+        }
+
+        transport
+    }
+
+    /// Returns a [`SearchBuilder`](SearchBuilder) for configuring a new client.
+    pub fn builder() -> SearchBuilder {
+        SearchBuilder::new()
+    }
+
+    /// Returns this client's page cache, if enabled.
+    pub fn cache(&self) -> Option<&Arc<PageCache>> {
+        self.cache.as_ref()
+    }
+
+    /// Search SunXDCC for the given `query`, using this client's configuration.
+    ///
+    /// If this client [parses excluded terms](SearchBuilder::parse_excluded_terms),
+    /// any trailing words in `query` with a leading `-` are removed from the
+    /// query sent, and results whose filenames contain them are excluded.
+    ///
+    /// See [`search`](search) for details.
+    pub fn search(&self, query: &str) -> SearchResults {
+        let mut results = SearchResults::new(
+            self.transport(),
+            query,
+            self.page_capacity,
+            self.query_options.clone(),
+        );
+        results.max_pages = self.max_pages;
+        results.trim_fields = self.trim_fields;
+        results.circuit_breaker = self.circuit_breaker.clone();
+        results.deadline = self.deadline.map(|deadline| Instant::now() + deadline);
+        results.request_timeout = self.request_timeout;
+        results.base_url = self.base_url.clone();
+        results.query_encoding = self.query_encoding;
+        results.cache = self.cache.clone();
+        results.max_response_bytes = self.max_response_bytes;
+        results.error_on_empty = self.error_on_empty;
+        results.on_page = self.on_page.clone();
+        results.on_page_error = self.on_page_error.clone();
+        results.empty_page_tolerance = self.empty_page_tolerance;
+        results.on_raw_response = self.on_raw_response.clone();
+        results
+    }
+}
+
+/// How a search's query is validated and prepared before being sent, and how
+/// its results are filtered.
+#[cfg(feature = "blocking")]
+#[derive(Clone, Debug)]
+struct QueryOptions {
+    /// The minimum and maximum query lengths.
+    len: (usize, usize),
+    /// Whether to normalize queries before sending them.
+    normalize: bool,
+    /// Whether to strip diacritics from queries before sending them.
+    #[cfg(feature = "unicode")]
+    strip_diacritics: bool,
+    /// Terms whose presence in a filename excludes a result, lowercased.
+    excluded_terms: Vec<String>,
+    /// Whether words with a leading `-` in queries are excluded terms.
+    parse_excluded_terms: bool,
+    /// Whether results must contain every term of the query.
+    require_all_terms: bool,
+}
+
+#[cfg(feature = "blocking")]
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self {
+            len: (DEFAULT_MIN_QUERY_LEN, DEFAULT_MAX_QUERY_LEN),
+            normalize: false,
+            #[cfg(feature = "unicode")]
+            strip_diacritics: false,
+            excluded_terms: vec![],
+            parse_excluded_terms: false,
+            require_all_terms: false,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl QueryOptions {
+    /// Returns `query` as it should be sent.
+    fn prepare(&self, query: &str) -> String {
+        // Stray whitespace (e.g. a trailing newline) can cause SunXDCC to
+        // silently return no results, so never send any.
+        let query = match self.normalize {
+            true => normalize_query(query),
+            false => collapse_whitespace(query),
+        };
+
+        #[cfg(feature = "unicode")]
+        if self.strip_diacritics {
+            return strip_diacritics(&query);
+        }
+
+        query
+    }
+}
+
+/// Information about a successfully fetched page, for progress reporting.
+///
+/// See [`SearchBuilder::on_page`](SearchBuilder::on_page).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageInfo {
+    /// The index of the page.
+    pub page: usize,
+    /// The number of results in the page.
+    pub results: usize,
+    /// The number of results fetched so far, including this page's.
+    pub total_results: usize,
+    /// The time taken to fetch the page.
+    pub elapsed: Duration,
+}
+
+/// A user-supplied callback, shared by a client and its searches.
+#[cfg(feature = "blocking")]
+struct Callback<F: ?Sized>(Arc<F>);
+
+#[cfg(feature = "blocking")]
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
+/// A callback invoked after each successful page fetch.
+#[cfg(feature = "blocking")]
+type PageHook = Callback<dyn Fn(&PageInfo) + Send + Sync>;
+
+/// A callback invoked after each failed page fetch, with the page's index.
+#[cfg(feature = "blocking")]
+type PageErrorHook = Callback<dyn Fn(usize, &Error) + Send + Sync>;
+
+/// A callback invoked with each raw response body, before it's parsed.
+#[cfg(feature = "blocking")]
+type RawResponseHook = Callback<dyn Fn(&[u8]) + Send + Sync>;
+
+/// A handle for cancelling a search, possibly from another thread.
+///
+/// See [`SearchResults::cancellable`](SearchResults::cancellable).
+#[cfg(feature = "blocking")]
+#[derive(Clone, Debug, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+#[cfg(feature = "blocking")]
+impl CancelHandle {
+    /// Cancel the associated search.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the associated search has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// A checkpoint of a search's progress, for resuming it later.
+///
+/// See [`SearchResults::state`](SearchResults::state),
+/// [`SearchResults::resume`](SearchResults::resume), and
+/// [`SearchResults::from_state`](SearchResults::from_state).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchState {
+    /// The search query.
+    pub query: String,
+    /// The next page to be fetched.
+    pub page: usize,
+    /// The number of results yielded so far.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub yielded: usize,
+    /// Results that were fetched but not yet yielded, in yield order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub buffered: Vec<SearchResult>,
+    /// Terms whose presence in a filename excludes a result, lowercased.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub excluded_terms: Vec<String>,
+}
+
+/// A stateful iteration container for search results.
+#[cfg(feature = "blocking")]
+pub struct SearchResults {
+    /// The transport to use for all requests.
+    transport: Box<dyn Transport>,
+    /// The search query.
+    query: String,
+    /// The current result page.
+    current_page: usize,
+    /// The current list of results.
+    current_results: Vec<SearchResult>,
+    /// The maximum number of bytes to read from a single response.
+    max_response_bytes: usize,
+    /// The time taken by each successful page fetch, in order.
+    page_durations: Vec<Duration>,
+    /// The number of rate-limited requests that may still be retried.
+    retries_remaining: u32,
+    /// The URL of the most recently fetched page.
+    last_url: Option<Url>,
+    /// The circuit breaker guarding page fetches, if any.
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// The number of results yielded so far.
+    yielded: usize,
+    /// The handle that cancels this search, if cancellable.
+    cancel: Option<CancelHandle>,
+    /// The time after which no more pages will be fetched, if any.
+    deadline: Option<Instant>,
+    /// The timeout for each request, which is shortened to fit within our
+    /// deadline.
+    request_timeout: Duration,
+    /// Whether the search was cut short by its deadline or page limit.
+    truncated: bool,
+    /// The page that the current results were fetched from.
+    buffered_page: usize,
+    /// The API endpoint to query.
+    base_url: Url,
+    /// How spaces in the query are encoded.
+    query_encoding: QueryEncoding,
+    /// The cache to consult before fetching pages, if any.
+    cache: Option<Arc<PageCache>>,
+    /// Whether a page with no results has been observed.
+    finished: bool,
+    /// Whether a search with no results produces an error.
+    error_on_empty: bool,
+    /// The callback for successful page fetches, if any.
+    on_page: Option<PageHook>,
+    /// The callback for failed page fetches, if any.
+    on_page_error: Option<PageErrorHook>,
+    /// The reason the query is invalid, if it is. This is yielded in place of
+    /// the first page, and again whenever the search is restarted.
+    query_error: Option<Error>,
+    /// The number of pages fetched successfully.
+    pages_fetched: usize,
+    /// The number of times to re-fetch an empty page.
+    empty_page_tolerance: u32,
+    /// The callback for raw response bodies, if any.
+    on_raw_response: Option<RawResponseHook>,
+    /// The query as originally given, if it differs from the one sent.
+    original_query: Option<String>,
+    /// Terms whose presence in a filename excludes a result, lowercased.
+    excluded_terms: Vec<String>,
+    /// Terms that must all be present in a filename, as produced by
+    /// `separate_words`.
+    required_terms: Vec<String>,
+    /// The maximum number of pages to fetch, if set explicitly. Otherwise,
+    /// `DEFAULT_MAX_PAGES` applies to iteration, but not to the consuming
+    /// methods that need every result.
+    max_pages: Option<usize>,
+    /// Whether to trim whitespace from every field of each result.
+    trim_fields: bool,
+    /// How the query is validated and prepared.
+    query_options: QueryOptions,
+    /// The delay before each page fetch after the first, if any.
+    page_delay: Option<Duration>,
+}
+
+#[cfg(feature = "blocking")]
+impl SearchResults {
+    /// Create a new `SearchResults` for `query` that fetches pages via the
+    /// given [`Transport`](Transport) rather than the default HTTP client.
+    pub fn with_transport(transport: Box<dyn Transport>, query: impl Into<String>) -> Self {
+        // Each query returns a maximum number of 50 results, so reserve at least
+        // that many elements in our `current_results` buffer.
+        Self::new(transport, query, PAGE_SIZE, QueryOptions::default())
+    }
+
+    fn new(
+        transport: Box<dyn Transport>,
+        query: impl Into<String>,
+        capacity: usize,
+        query_options: QueryOptions,
+    ) -> Self {
+        let mut results = Self {
+            transport: transport,
+            query: String::new(),
+            current_page: 0,
+            current_results: Vec::with_capacity(capacity),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            page_durations: vec![],
+            retries_remaining: 0,
+            last_url: None,
+            circuit_breaker: None,
+            yielded: 0,
+            cancel: None,
+            deadline: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            truncated: false,
+            buffered_page: 0,
+            base_url: default_base_url(),
+            query_encoding: QueryEncoding::default(),
+            cache: None,
+            finished: false,
+            error_on_empty: false,
+            on_page: None,
+            on_page_error: None,
+            query_error: None,
+            pages_fetched: 0,
+            empty_page_tolerance: 0,
+            on_raw_response: None,
+            original_query: None,
+            excluded_terms: vec![],
+            required_terms: vec![],
+            max_pages: None,
+            trim_fields: false,
+            query_options: query_options,
+            page_delay: None,
+        };
+
+        results.set_query(query.into());
+        results
+    }
+
+    /// Set this search's query, validating and preparing it according to our
+    /// query options.
+    fn set_query(&mut self, query: String) {
+        let options = &self.query_options;
+        let (positive, excluded) = match options.parse_excluded_terms {
+            true => split_excluded_terms(&query),
+            false => (query.clone(), vec![]),
+        };
+        let prepared = options.prepare(&positive);
+
+        // Only the positive part is sent, so e.g. `-720p` alone is empty.
+        let (min_len, max_len) = options.len;
+        self.query_error = validate_query(&positive, min_len, max_len).err();
+        self.excluded_terms = options
+            .excluded_terms
+            .iter()
+            .cloned()
+            .chain(excluded)
+            .collect();
+        self.required_terms = match options.require_all_terms {
+            true => prepared
+                .split_whitespace()
+                .map(|word| collapse_whitespace(&separate_words(&word.to_lowercase())))
+                .filter(|term| !term.is_empty())
+                .collect(),
+            false => vec![],
+        };
+
+        self.original_query = (prepared != query).then_some(query);
+        self.query = prepared;
+    }
+
+    /// Replace this search's query, and restart it from the first page.
+    ///
+    /// Any buffered results for the previous query are discarded, as are its
+    /// statistics (such as [`pages_fetched`](SearchResults::pages_fetched)).
+    /// The new query is validated and prepared in the same way as the
+    /// original, and the search's transport, cache, and other configuration
+    /// are kept, so this avoids the cost of setting up a new client.
+    pub fn reset(&mut self, query: impl Into<String>) {
+        self.set_query(query.into());
+        self.restart();
+        self.page_durations.clear();
+        self.last_url = None;
+        self.truncated = false;
+        self.buffered_page = 0;
+    }
+
+    /// Create a new `SearchResults` for `query` that serves pages previously
+    /// recorded with [`record_to`](SearchResults::record_to), without touching
+    /// the network.
+    ///
+    /// Replaying a page whose recording was made for a different query or page
+    /// produces [`Error::Malformed`](Error::Malformed).
+    pub fn replay_from(dir: impl Into<PathBuf>, query: impl Into<String>) -> Self {
+        Self::with_transport(Box::new(ReplayTransport { dir: dir.into() }), query)
+    }
+
+    /// Record every page fetched by this search into the given directory,
+    /// for later use with [`replay_from`](SearchResults::replay_from).
+    ///
+    /// Each page is written to `page-N.rec`, containing the request URL and
+    /// page number followed by the raw response body. Existing recordings for
+    /// the same pages are overwritten.
+    pub fn record_to(mut self, dir: impl Into<PathBuf>) -> Self {
+        let inner = std::mem::replace(&mut self.transport, Box::new(StaticTransport::default()));
+        self.transport = Box::new(RecordingTransport {
+            inner: inner,
+            dir: dir.into(),
+        });
+        self
+    }
+
+    /// Resume a search from a checkpoint previously taken with
+    /// [`state`](SearchResults::state), using the given client's configuration.
+    pub fn from_state(state: SearchState, client: &SearchClient) -> Self {
+        let mut results = client.search(&state.query);
+        results.restore(state);
+        results
+    }
+
+    /// Resume a search from a checkpoint previously taken with
+    /// [`state`](SearchResults::state), using the default configuration.
+    ///
+    /// The resumed search continues exactly where the checkpointed one left
+    /// off, yielding any buffered results before fetching further pages.
+    pub fn resume(state: SearchState) -> Self {
+        let mut results = search(&state.query);
+        results.restore(state);
+        results
+    }
+
+    fn restore(&mut self, state: SearchState) {
+        self.current_page = state.page;
+        self.buffered_page = state.page.saturating_sub(1);
+        self.yielded = state.yielded;
+        for term in state.excluded_terms {
+            if !self.excluded_terms.contains(&term) {
+                self.excluded_terms.push(term);
+            }
+        }
+
+        // Our buffer is kept in reverse order, for popping.
+        self.current_results = state.buffered;
+        self.current_results.reverse();
+    }
+
+    /// Take a checkpoint of this search's progress.
+    ///
+    /// The checkpoint records the next page to be fetched, along with any
+    /// results from the current page that have been fetched but not yet
+    /// yielded, so that no results are skipped or repeated when resuming. It
+    /// also records the search's excluded terms, which aren't part of its query.
+    pub fn state(&self) -> SearchState {
+        SearchState {
+            query: self.query.clone(),
+            page: self.current_page,
+            yielded: self.yielded,
+            buffered: self.current_results.iter().rev().cloned().collect(),
+            excluded_terms: self.excluded_terms.clone(),
+        }
+    }
+
+    /// Returns the query for this search, as originally given.
+    ///
+    /// This may differ from the query actually sent if the search was created
+    /// by a client that normalizes queries; see
+    /// [`sent_query`](SearchResults::sent_query).
+    pub fn query(&self) -> &str {
+        self.original_query.as_deref().unwrap_or(&self.query)
+    }
+
+    /// Returns the query for this search, as sent to the API.
+    pub fn sent_query(&self) -> &str {
+        &self.query
+    }
+
+    /// Returns the number of results yielded so far.
+    pub fn yielded(&self) -> usize {
+        self.yielded
+    }
+
+    /// Returns the index of the next page to be fetched.
+    pub fn current_page(&self) -> usize {
+        self.current_page
+    }
+
+    /// Returns the number of pages fetched successfully so far.
+    ///
+    /// Unlike [`current_page`](SearchResults::current_page), this doesn't count
+    /// pages that were skipped or that failed to fetch, and does count the
+    /// final, empty page.
+    pub fn pages_fetched(&self) -> usize {
+        self.pages_fetched
+    }
+
+    /// Take all currently buffered results, in the order they would have been
+    /// yielded, without fetching another page.
+    ///
+    /// Drained results count as yielded. The next call to `next` fetches the
+    /// following page; see also [`page_iter`](SearchResults::page_iter), which
+    /// yields entire pages at a time.
+    pub fn drain(&mut self) -> Vec<SearchResult> {
+        let mut drained = std::mem::take(&mut self.current_results);
+        drained.reverse();
+        self.yielded += drained.len();
+        drained
+    }
+
+    /// Restart this search from the first page, discarding any buffered results.
+    ///
+    /// If the query is invalid, restarting yields the same error again, without
+    /// making any requests.
+    pub fn restart(&mut self) {
+        self.current_page = 0;
+        self.current_results.clear();
+        self.yielded = 0;
+        self.pages_fetched = 0;
+        self.finished = false;
+    }
+
+    /// Skip the next `n` pages without fetching them, discarding any buffered
+    /// results.
+    ///
+    /// The next page fetched will be the current page plus `n`.
+    pub fn skip_pages(&mut self, n: usize) {
+        self.current_page += n;
+        self.current_results.clear();
+        self.finished = false;
+    }
+
+    /// Returns whether this search has reached the end of its results, i.e.
+    /// whether a page with no results has been observed.
+    ///
+    /// This never fetches; once it returns `true`, iteration yields only the
+    /// results that remain buffered, if any.
+    pub fn is_exhausted(&self) -> bool {
+        self.finished
+    }
+
+    /// Make this search cancellable, returning it along with a handle that
+    /// cancels it.
+    ///
+    /// Once cancelled, iteration ends: the next call to `next` returns `None`
+    /// without issuing further requests, and any wait before retrying a
+    /// rate-limited request is cut short. A request that is already in flight
+    /// when the search is cancelled is not interrupted, but its results are
+    /// discarded.
+    ///
+    /// ```no_run
+    /// # use std::thread;
+    /// let (results, cancel) = sunxdcc::search("hitchhiker's guide").cancellable();
+    ///
+    /// let worker = thread::spawn(move || results.count());
+    /// cancel.cancel();
+    /// worker.join().unwrap();
+    /// ```
+    pub fn cancellable(mut self) -> (Self, CancelHandle) {
+        let handle = self
+            .cancel
+            .get_or_insert_with(CancelHandle::default)
+            .clone();
+        (self, handle)
+    }
+
+    /// Cancel this search once `token` is set to `true`, as with
+    /// [`cancellable`](SearchResults::cancellable).
+    ///
+    /// This is convenient when one flag should cancel several searches, or
+    /// when the flag already exists. It replaces any existing cancellation
+    /// handle.
+    ///
+    /// ```
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use std::sync::Arc;
+    /// # use sunxdcc::{SearchResults, StaticTransport};
+    /// let page = br##"{"network":["irc.foo.net","irc.foo.net"],"channel":["#foo","#foo"],
+    ///     "bot":["foobot","foobot"],"fsize":["[1M]","[2M]"],"fname":["a.txt","b.txt"],
+    ///     "packnum":["#1","#2"],"gets":["3x","5x"],"botrec":["Na","Na"]}"##;
+    /// let transport = StaticTransport::new(vec![page.to_vec()]);
+    ///
+    /// let token = Arc::new(AtomicBool::new(false));
+    /// let mut results = SearchResults::with_transport(Box::new(transport), "foo")
+    ///     .with_cancel_token(token.clone());
+    /// assert!(results.next().is_some());
+    ///
+    /// token.store(true, Ordering::SeqCst);
+    /// assert!(results.next().is_none());
+    /// ```
+    pub fn with_cancel_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(CancelHandle(token));
+        self
+    }
+
+    /// Returns whether this search has been cancelled.
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelHandle::is_cancelled)
+    }
+
+    /// Returns whether this search's deadline has passed.
+    fn deadline_exceeded(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Returns whether this search has fetched as many pages as it's allowed to.
+    fn page_limit_reached(&self) -> bool {
+        self.pages_fetched >= self.max_pages.unwrap_or(DEFAULT_MAX_PAGES)
+    }
+
+    /// Lift the default page limit, for methods that need every result. A
+    /// limit set explicitly still applies.
+    pub(crate) fn without_default_page_limit(mut self) -> Self {
+        self.max_pages = Some(self.max_pages.unwrap_or(usize::MAX));
+        self
+    }
+
+    /// Returns whether this search was cut short by its deadline or its page
+    /// limit.
+    ///
+    /// See [`SearchBuilder::deadline`](SearchBuilder::deadline) and
+    /// [`SearchBuilder::max_pages`](SearchBuilder::max_pages).
+    pub fn was_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Sleep for the given duration, waking early if this search is cancelled
+    /// or its deadline passes.
+    fn sleep(&self, duration: Duration) -> Result<(), Error> {
+        let wake = Instant::now() + duration;
+
+        loop {
+            if self.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            if self.deadline_exceeded() {
+                return Err(Error::DeadlineExceeded);
+            }
+
+            let remaining = wake.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+
+            thread::sleep(remaining.min(CANCEL_POLL_INTERVAL));
+        }
+    }
+
+    /// Set the number of times that rate-limited requests may be retried.
+    ///
+    /// This is an overall budget for the lifetime of the search, not a per-page
+    /// limit. Each retry sleeps for the duration given by the server's
+    /// `Retry-After` header, or one second if none is given. Once the budget is
+    /// exhausted, rate-limited requests produce
+    /// [`Error::RateLimited`](Error::RateLimited).
+    ///
+    /// The default budget is zero, meaning that no retries are attempted.
+    pub fn retry_budget(mut self, retries: u32) -> Self {
+        self.retries_remaining = retries;
+        self
+    }
+
+    /// Control whether leading and trailing whitespace is trimmed from every
+    /// field of each result; see
+    /// [`SearchBuilder::trim_fields`](SearchBuilder::trim_fields).
+    ///
+    /// This only affects pages fetched after it's called.
+    ///
+    /// ```
+    /// # use sunxdcc::{SearchResults, StaticTransport};
+    /// let page = br##"{"network":[" irc.foo.net "],"channel":["#foo\t"],"bot":["foobot"],
+    ///     "fsize":[" [1M]"],"fname":["  a.txt  "],"packnum":["#1 "],"gets":["3x"],
+    ///     "botrec":["Na"]}"##;
+    /// let transport = StaticTransport::new(vec![page.to_vec()]);
+    ///
+    /// let result = SearchResults::with_transport(Box::new(transport), "foo")
+    ///     .trim_fields(true)
+    ///     .next()
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(&*result.network, "irc.foo.net");
+    /// assert_eq!(&*result.channel, "#foo");
+    /// assert_eq!(result.filesize, "[1M]");
+    /// assert_eq!(result.filename, "a.txt");
+    /// assert_eq!(result.packet_number, "#1");
+    /// ```
+    pub fn trim_fields(mut self, trim: bool) -> Self {
+        self.trim_fields = trim;
+        self
+    }
+
+    /// Fetch the body for the given URL, retrying rate-limited requests
+    /// while our retry budget allows.
+    ///
+    /// If we have a deadline, each request's timeout is shortened to the time
+    /// remaining before it.
+    fn get_page(&mut self, url: &Url) -> Result<Vec<u8>, Error> {
+        loop {
+            let timeout = match self.deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => {
+                        Some(remaining.min(self.request_timeout))
+                    }
+                    _ => return Err(Error::DeadlineExceeded),
+                },
+                None => None,
+            };
+
+            match transport::get_page(&*self.transport, url, self.max_response_bytes, timeout) {
+                Err(Error::RateLimited { retry_after }) if self.retries_remaining > 0 => {
+                    self.retries_remaining -= 1;
+                    self.sleep(retry_after.unwrap_or(DEFAULT_RETRY_DELAY))?;
+                }
+                // A request that failed because it ran into our deadline
+                // (typically by timing out) just means that we're out of time.
+                Err(_) if self.deadline_exceeded() => return Err(Error::DeadlineExceeded),
+                result => return result,
+            }
+        }
+    }
+
+    /// Refresh our internal state, fetching more results from the API if available.
+    ///
+    /// This function doesn't check whether the current results have been fully consumed;
+    /// callers must take care to fully consume all current results to avoid silently
+    /// skipping results.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(query = %self.query, current_page = self.current_page, result_count)
+        )
+    )]
+    fn refresh(&mut self) -> Result<(), Error> {
+        // The error is kept, so that restarting the search (or skipping pages)
+        // can't send the invalid query.
+        if let Some(e) = &self.query_error {
+            self.finished = true;
+            return Err(match e {
+                Error::InvalidQuery { reason } => Error::InvalidQuery {
+                    reason: reason.clone(),
+                },
+                // `validate_query` produces no other errors.
+                _ => Error::EmptyQuery,
+            });
+        }
+
+        // Every fetch is delayed, including those made back to back when a
+        // page's results are all filtered out. A cancelled (or expired) sleep
+        // ends the search.
+        if let Some(delay) = self.page_delay.filter(|_| self.pages_fetched > 0) {
+            self.sleep(delay)?;
+        }
+
+        debug!(
+            "fetching page {} for query {:?}",
+            self.current_page, self.query
+        );
+
+        let result = match self.circuit_breaker.clone() {
+            Some(breaker) => breaker.check().and_then(|()| {
+                let result = self.fetch_page();
+                breaker.record(result.is_ok());
+                result
+            }),
+            None => self.fetch_page(),
+        };
+
+        if result.is_ok() {
+            self.pages_fetched += 1;
+            debug!(
+                "received {} results for page {}",
+                self.current_results.len(),
+                self.buffered_page
+            );
+
+            #[cfg(feature = "tracing")]
+            tracing::Span::current().record("result_count", self.current_results.len());
+        }
+
+        match (&result, &self.on_page, &self.on_page_error) {
+            (Ok(()), Some(on_page), _) => (on_page.0)(&PageInfo {
+                page: self.buffered_page,
+                results: self.current_results.len(),
+                total_results: self.yielded + self.current_results.len(),
+                elapsed: self.last_page_duration().unwrap_or_default(),
+            }),
+            (Err(e), _, Some(on_page_error)) => (on_page_error.0)(self.current_page, e),
+            _ => {}
+        }
+
+        result
+    }
+
+    /// Produce the next result, fetching more pages as needed.
+    fn next_result(&mut self) -> Option<Result<SearchResult, Error>> {
+        if self.is_cancelled() {
+            return None;
+        }
+
+        // A page may be non-empty but have all of its results excluded, so
+        // keep fetching until we have something to yield.
+        while self.needs_refresh() {
+            if self.finished {
+                return None;
+            }
+
+            if self.deadline_exceeded() || self.page_limit_reached() {
+                self.truncated = true;
+                return None;
+            }
+
+            let result = self.refresh();
+
+            // If we were cancelled mid-request, discard whatever we received.
+            if self.is_cancelled() {
+                return None;
+            }
+
+            match result {
+                Err(Error::DeadlineExceeded) => {
+                    self.truncated = true;
+                    return None;
+                }
+                Err(e) => return Some(Err(e)),
+                Ok(()) => {}
+            }
+
+            // `finished` is set now, so this is only produced once.
+            if self.finished && self.error_on_empty && self.yielded == 0 {
+                return Some(Err(Error::NoResults));
+            }
+        }
+
+        // NOTE: This produces results in the correct order, despite the `pop`.
+        // See the implementation of RawResult::consume.
+        let result = self.current_results.pop()?;
+        self.yielded += 1;
+
+        Some(Ok(result))
+    }
+
+    /// Fetch the next page of results into our internal state.
+    fn fetch_page(&mut self) -> Result<(), Error> {
+        let start = Instant::now();
+        self.current_results.clear();
+
+        let url = page_url(
+            &self.base_url,
+            &self.query,
+            self.current_page,
+            self.query_encoding,
+        );
+
+        let mut empty_retries = self.empty_page_tolerance;
+        loop {
+            let cached = self.cache.as_ref().and_then(|cache| cache.get(&url));
+            let fresh = cached.is_none();
+            let body = match cached {
+                Some(body) => body,
+                None => self.get_page(&url)?,
+            };
+
+            if let Some(on_raw_response) = &self.on_raw_response {
+                (on_raw_response.0)(&body);
+            }
+
+            serde_json::from_slice::<RawResult>(&body)
+                .map_err(|e| Error::Malformed(e.to_string()))?
+                .consume(&mut self.current_results, self.trim_fields)?;
+
+            // An empty page may be transient, so don't trust (or cache) it
+            // until we've run out of retries.
+            if fresh && self.current_results.is_empty() && empty_retries > 0 {
+                empty_retries -= 1;
+                self.sleep(EMPTY_PAGE_RETRY_DELAY)?;
+                continue;
+            }
+
+            // Only cache pages that were fetched and parsed successfully.
+            if let (true, Some(cache)) = (fresh, &self.cache) {
+                cache.insert(&url, body);
+            }
+
+            break;
+        }
+
+        // Only an empty response marks the end of the results, so this must
+        // happen before any results are excluded.
+        self.finished = self.current_results.is_empty();
+        if !self.excluded_terms.is_empty() || !self.required_terms.is_empty() {
+            let (excluded, required) = (&self.excluded_terms, &self.required_terms);
+            self.current_results
+                .retain(|result| filename_matches(&result.filename, excluded, required));
+        }
+
+        self.buffered_page = self.current_page;
+        self.current_page += 1;
+        self.page_durations.push(start.elapsed());
+        self.last_url = Some(url);
+
+        Ok(())
+    }
+
+    /// Returns the API URL of the most recently fetched page, if any pages have
+    /// been fetched successfully.
+    ///
+    /// This is useful for reproducing unexpected results outside of this crate.
+    ///
+    /// ```
+    /// # use sunxdcc::{SearchResults, StaticTransport};
+    /// let transport = Box::new(StaticTransport::default());
+    /// let mut results = SearchResults::with_transport(transport, "foo bar & baz");
+    /// assert!(results.next().is_none());
+    /// assert_eq!(
+    ///     results.last_url().unwrap().as_str(),
+    ///     "https://sunxdcc.com/deliver.php?sterm=foo+bar+%26+baz&page=0"
+    /// );
+    /// ```
+    pub fn last_url(&self) -> Option<&Url> {
+        self.last_url.as_ref()
+    }
+
+    /// Returns the average time taken to fetch a page of results, if any
+    /// pages have been fetched successfully.
+    pub fn average_page_duration(&self) -> Option<Duration> {
+        if self.page_durations.is_empty() {
+            return None;
+        }
+
+        let total: Duration = self.page_durations.iter().sum();
+        Some(total / self.page_durations.len() as u32)
+    }
+
+    /// Returns the time taken to fetch the most recent page of results, if any
+    /// pages have been fetched successfully.
+    pub fn last_page_duration(&self) -> Option<Duration> {
+        self.page_durations.last().copied()
+    }
+
+    /// Returns whether the next call to `next` will fetch a new page, i.e.
+    /// whether we're just starting or we've exhausted our current results.
+    fn needs_refresh(&self) -> bool {
+        self.current_page == 0 || self.current_results.is_empty()
+    }
+
+    /// Wrap this `SearchResults` in an iterator that sleeps for `delay` before
+    /// fetching each page after the first.
+    ///
+    /// The delay is applied between page fetches, not between individual results,
+    /// including when several pages are fetched for a single result because
+    /// every result on a page was excluded.
+    ///
+    /// This is a courtesy delay intended to avoid hammering the SunXDCC API with
+    /// rapid sequential requests; it is **not** a guarantee of compliance with
+    /// any rate limits or terms of service.
+    pub fn with_rate_limit(mut self, delay: Duration) -> RateLimitedSearchResults {
+        self.page_delay = Some(delay);
+        RateLimitedSearchResults { inner: self }
+    }
+
+    /// Filter this search's results to those whose filename contains `needle`,
+    /// ignoring case. Errors are passed through.
+    pub fn filename_contains(
+        self,
+        needle: &str,
+    ) -> impl Iterator<Item = Result<SearchResult, Error>> {
+        let needle = needle.to_lowercase();
+
+        self.filter(move |result| match result {
+            Ok(result) => result.filename.to_lowercase().contains(&needle),
+            Err(_) => true,
+        })
+    }
+
+    /// Filter this search's results to the first result for each distinct
+    /// filename, ignoring case. Errors are passed through.
+    ///
+    /// Unlike [`search_multi`](search_multi)'s deduplication, this ignores
+    /// everything but the filename, so the same file offered by several bots
+    /// (or at several sizes) is only yielded once.
+    pub fn unique_filenames(self) -> impl Iterator<Item = Result<SearchResult, Error>> {
+        let mut seen: HashSet<String> = HashSet::new();
+
+        self.filter(move |result| match result {
+            Ok(result) => seen.insert(result.filename.to_lowercase()),
+            Err(_) => true,
+        })
+    }
+
+    /// Convert this `SearchResults` into an iterator over entire pages of
+    /// results, rather than individual results.
+    ///
+    /// If some results from the current page are still buffered, they are
+    /// yielded as the first page.
+    pub fn page_iter(self) -> PageIter {
+        PageIter {
+            inner: self,
+            finished: false,
+        }
+    }
+
+    /// Annotate each result with the index of the API page it was fetched from.
+    ///
+    /// The index is that of the page actually requested, so it reflects any
+    /// pages skipped with [`skip_pages`](SearchResults::skip_pages) or restored
+    /// from a [`SearchState`](SearchState).
+    pub fn with_pages(mut self) -> impl Iterator<Item = Result<(usize, SearchResult), Error>> {
+        iter::from_fn(move || {
+            let result = self.next()?;
+            Some(result.map(|result| (self.buffered_page, result)))
+        })
+    }
+
+    /// Annotate each result with the zero-based index of the API page it was
+    /// fetched from.
+    ///
+    /// This is an alias for [`with_pages`](SearchResults::with_pages); errors
+    /// are passed through without a page index.
+    pub fn with_page(self) -> impl Iterator<Item = Result<(usize, SearchResult), Error>> {
+        self.with_pages()
+    }
+
+    /// Consume this `SearchResults`, returning up to its first `n` results.
+    ///
+    /// Pages are only fetched as needed, so in the common case this fetches
+    /// `ceil(n / 50)` pages (plus one more, if the results run out first).
+    /// Returns early on the first error.
+    pub fn take_n(self, n: usize) -> Result<Vec<SearchResult>, Error> {
+        self.take(n).collect()
+    }
+
+    /// Consume this `SearchResults`, returning the result with the largest file.
+    ///
+    /// Sizes are compared by [`SearchResult::filesize_bytes`], with malformed
+    /// sizes ranked below all others; ties are broken in favor of the earliest
+    /// result. Only the current largest result is kept while iterating.
+    ///
+    /// This drains the entire iterator, returning early on the first error.
+    /// The default page limit doesn't apply; see
+    /// [`SearchBuilder::max_pages`](SearchBuilder::max_pages).
+    pub fn max_by_size(self) -> Result<Option<SearchResult>, Error> {
+        let mut largest: Option<SearchResult> = None;
+
+        for result in self.without_default_page_limit() {
+            let result = result?;
+            match &largest {
+                Some(current) if current.filesize_bytes() >= result.filesize_bytes() => {}
+                _ => largest = Some(result),
+            }
+        }
+
+        Ok(largest)
+    }
+
+    /// Consume this `SearchResults`, returning the best result by `criterion`.
+    ///
+    /// Results whose value for the criterion is unknown or malformed are
+    /// ranked below all others, and ties are broken in favor of the earliest
+    /// result. Only the current best result is kept while iterating.
+    ///
+    /// This drains the entire iterator, returning early on the first error.
+    /// The default page limit doesn't apply; see
+    /// [`SearchBuilder::max_pages`](SearchBuilder::max_pages).
+    ///
+    /// ```
+    /// # use sunxdcc::{Criterion, SearchResults, StaticTransport};
+    /// let page = |name: &str, gets: &str| {
+    ///     format!(
+    ///         r##"{{"network":["irc.foo.net"],"channel":["#foo"],"bot":["foobot"],
+    ///         "fsize":["[1M]"],"fname":["{name}"],"packnum":["#1"],"gets":["{gets}"],
+    ///         "botrec":["Na"]}}"##
+    ///     )
+    ///     .into_bytes()
+    /// };
+    /// let transport = StaticTransport::new(vec![page("a.txt", "3x"), page("b.txt", "7x")]);
+    ///
+    /// let best = SearchResults::with_transport(Box::new(transport), "foo")
+    ///     .best(Criterion::Downloads)
+    ///     .unwrap();
+    /// assert_eq!(best.unwrap().filename, "b.txt");
+    /// ```
+    pub fn best(self, criterion: Criterion) -> Result<Option<SearchResult>, Error> {
+        let mut best: Option<(Option<u64>, SearchResult)> = None;
+
+        for result in self.without_default_page_limit() {
+            let result = result?;
+            let value = criterion.value(&result);
+            match &best {
+                Some((current, _)) if *current >= value => {}
+                _ => best = Some((value, result)),
+            }
+        }
+
+        Ok(best.map(|(_, result)| result))
+    }
+
+    /// Consume this `SearchResults`, returning its best `n` results by
+    /// `criterion`, best first.
+    ///
+    /// Ranking is as for [`best`](SearchResults::best), including the
+    /// tie-breaking and the page limit; likewise, this drains the entire
+    /// iterator, returning early on the first error.
+    pub fn top_n(self, n: usize, criterion: Criterion) -> Result<Vec<SearchResult>, Error> {
+        let mut results = self
+            .without_default_page_limit()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // `sort_by_key` is stable, so ties stay in their original order.
+        results.sort_by_key(|result| cmp::Reverse(criterion.value(result)));
+        results.truncate(n);
+
+        Ok(results)
+    }
+
+    /// Consume this `SearchResults`, collecting its results sorted by
+    /// download count, most downloaded first.
+    ///
+    /// Counts are parsed with
+    /// [`SearchResult::download_count_value`], with malformed counts treated
+    /// as zero. The sort is stable, so results with equal counts keep their
+    /// original order.
+    ///
+    /// This drains the entire iterator, returning early on the first error.
+    /// The default page limit doesn't apply; see
+    /// [`SearchBuilder::max_pages`](SearchBuilder::max_pages).
+    pub fn collect_sorted_by_downloads(self) -> Result<Vec<SearchResult>, Error> {
+        let mut results = self
+            .without_default_page_limit()
+            .collect::<Result<Vec<_>, _>>()?;
+        results
+            .sort_by_key(|result| cmp::Reverse(result.download_count_value().unwrap_or_default()));
+        Ok(results)
+    }
+
+    /// Consume this `SearchResults`, returning the total size of its results'
+    /// files in bytes.
+    ///
+    /// Sizes are parsed with [`SearchResult::filesize_bytes`], with malformed
+    /// sizes counted as zero. Since SunXDCC reports sizes approximately, so is
+    /// the total.
+    ///
+    /// This drains the entire iterator, returning early on the first error.
+    /// The default page limit doesn't apply; see
+    /// [`SearchBuilder::max_pages`](SearchBuilder::max_pages).
+    pub fn total_bytes(self) -> Result<u64, Error> {
+        self.without_default_page_limit()
+            .try_fold(0, |total, result| {
+                Ok(total + result?.filesize_bytes().unwrap_or_default())
+            })
+    }
+
+    /// Consume this `SearchResults`, collecting its results into a map ordered
+    /// by packet number.
+    ///
+    /// Results whose packet numbers don't parse are skipped. Packet numbers are
+    /// only unique per bot, so results from different bots with the same packet
+    /// number collide; the last such result is kept.
+    ///
+    /// This drains the entire iterator, returning early on the first error.
+    /// The default page limit doesn't apply; see
+    /// [`SearchBuilder::max_pages`](SearchBuilder::max_pages).
+    pub fn by_packet_number(self) -> Result<BTreeMap<u32, SearchResult>, Error> {
+        let mut packets = BTreeMap::new();
+
+        for result in self.without_default_page_limit() {
+            let result = result?;
+            if let Some(number) = result
+                .packet_number_u64()
+                .ok()
+                .and_then(|n| u32::try_from(n).ok())
+            {
+                packets.insert(number, result);
+            }
+        }
+
+        Ok(packets)
+    }
+
+    /// Consume this `SearchResults`, grouping each result by its IRC network.
+    ///
+    /// Network names are normalized (lowercased) before grouping, so the keys
+    /// of the returned map are the lowercased networks. Results within each group
+    /// are kept in the order they were returned by the API.
+    ///
+    /// This drains the entire iterator, returning early on the first error.
+    /// The default page limit doesn't apply; see
+    /// [`SearchBuilder::max_pages`](SearchBuilder::max_pages).
+    pub fn group_by_network(self) -> Result<HashMap<String, Vec<SearchResult>>, Error> {
+        let mut groups: HashMap<String, Vec<SearchResult>> = HashMap::new();
+
+        for result in self.without_default_page_limit() {
+            let result = result?;
+            groups
+                .entry(result.network.to_lowercase())
+                .or_default()
+                .push(result);
+        }
+
+        Ok(groups)
+    }
+
+    /// Consume this `SearchResults`, writing each result to `w` as a JSON
+    /// object on its own line (i.e., as [JSON Lines](https://jsonlines.org/)).
+    ///
+    /// Results are written as they're fetched, so output begins before the
+    /// search completes. Writing stops at the first error, after flushing
+    /// everything written so far.
+    ///
+    /// ```no_run
+    /// # use std::io;
+    /// sunxdcc::search("the hitchhiker's guide to the galaxy")
+    ///     .write_jsonl(io::stdout().lock())
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn write_jsonl<W: std::io::Write>(self, mut w: W) -> Result<(), Error> {
+        let written = self.into_iter().try_for_each(|result| {
+            serde_json::to_writer(&mut w, &result?).map_err(|e| {
+                if e.is_io() {
+                    Error::Io(e.into())
+                } else {
+                    Error::Malformed(e.to_string())
+                }
+            })?;
+            w.write_all(b"\n")?;
+            Ok(())
+        });
+
+        w.flush()?;
+        written
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl fmt::Debug for SearchResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Summarize our state, rather than dumping the transport and buffer.
+        f.debug_struct("SearchResults")
+            .field("query", &self.query)
+            .field("page", &self.current_page)
+            .field("buffered", &self.current_results.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Iterator for SearchResults {
+    type Item = Result<SearchResult, Error>;
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip(self),
+            fields(query = %self.query, current_page = self.current_page, result_count)
+        )
+    )]
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_result();
+
+        // Unlike `refresh`'s, this span counts every result yielded so far.
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("result_count", self.yielded);
+
+        result
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl FusedIterator for SearchResults {}
+
+/// A [`SearchResults`](SearchResults) wrapper that delays between page fetches.
+///
+/// See [`SearchResults::with_rate_limit`](SearchResults::with_rate_limit).
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+pub struct RateLimitedSearchResults {
+    /// The underlying results, which apply the delay themselves.
+    inner: SearchResults,
+}
+
+#[cfg(feature = "blocking")]
+impl Iterator for RateLimitedSearchResults {
+    type Item = Result<SearchResult, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// An iterator over pages of search results.
+///
+/// See [`SearchResults::page_iter`](SearchResults::page_iter).
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+pub struct PageIter {
+    /// The underlying results.
+    inner: SearchResults,
+    /// Whether we've observed the end of the results.
+    finished: bool,
+}
+
+#[cfg(feature = "blocking")]
+impl Iterator for PageIter {
+    type Item = Result<Vec<SearchResult>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished || self.inner.is_cancelled() {
+            self.finished = true;
+            return None;
+        }
+
+        // As in `SearchResults::next`, skip over pages whose results were all
+        // excluded.
+        while self.inner.needs_refresh() {
+            if self.inner.finished {
+                self.finished = true;
+                return None;
+            }
+
+            if self.inner.deadline_exceeded() || self.inner.page_limit_reached() {
+                self.inner.truncated = true;
+                self.finished = true;
+                return None;
+            }
+
+            match self.inner.refresh() {
+                Err(Error::DeadlineExceeded) => {
+                    self.inner.truncated = true;
+                    self.finished = true;
+                    return None;
+                }
+                Err(e) => return Some(Err(e)),
+                Ok(()) => {}
+            }
+        }
+
+        // Our buffer is kept in reverse order, for popping.
+        let mut page = std::mem::take(&mut self.inner.current_results);
+        page.reverse();
+        self.inner.yielded += page.len();
+        Some(Ok(page))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl FusedIterator for PageIter {}
+
+/// Search SunXDCC for the given `query`.
+///
+/// The returned `SearchResults` is an [`Iterator`](Iterator) over individual
+/// [`SearchResult`](SearchResult) items.
+///
+/// Queries are validated before any request is made: a blank query yields a
+/// single [`Error::EmptyQuery`](Error::EmptyQuery), and one that is shorter
+/// than 2 or longer than 256 characters a single
+/// [`Error::InvalidQuery`](Error::InvalidQuery), and then the search ends:
+///
+/// ```
+/// # use sunxdcc::Error;
+/// let mut results = sunxdcc::search("   ");
+/// assert!(matches!(results.next(), Some(Err(Error::EmptyQuery))));
+/// assert!(results.next().is_none());
+/// assert_eq!(results.pages_fetched(), 0);
+/// ```
+///
+/// ```no_run
+/// # use sunxdcc;
+/// for result in sunxdcc::search("the hitchhiker's guide to the galaxy") {
+///     println!("{:?}", result.unwrap());
+/// }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn search(query: &str) -> SearchResults {
+    SearchResults::with_transport(Box::new(default_client()), query)
+}
+
+/// Returns the value of the environment variable `name`, or `None` if it's
+/// unset or empty.
+#[cfg(feature = "blocking")]
+fn env_var(name: &'static str) -> Result<Option<String>, Error> {
+    match env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(value)) => Err(Error::InvalidEnvVar {
+            name: name,
+            value: value.to_string_lossy().into(),
+        }),
+    }
+}
+
+/// Returns the HTTP client used by [`search`](search).
+#[cfg(feature = "blocking")]
+fn default_client() -> reqwest::blocking::Client {
+    let builder = reqwest::blocking::Client::builder().default_headers(HeaderMap::from_iter([(
+        ACCEPT,
+        HeaderValue::from_static(DEFAULT_ACCEPT),
+    )]));
+
+    #[cfg(feature = "cookies")]
+    let builder = builder.cookie_store(true);
+
+    // Building only fails if the TLS backend can't be initialized, in which
+    // case `Client::new` would have failed in the same way.
+    builder.build().unwrap_or_default()
+}
+
+/// Count the number of SunXDCC results for the given `query`.
+///
+/// This is a convenience over iterating a [`search`](search) and counting its
+/// results; it still issues one request per page of results, and returns the
+/// first error encountered. Every result is counted, regardless of the default
+/// page limit (see [`SearchBuilder::max_pages`](SearchBuilder::max_pages)).
+#[cfg(feature = "blocking")]
+pub fn count(query: &str) -> Result<usize, Error> {
+    search(query)
+        .without_default_page_limit()
+        .try_fold(0, |count, result| result.map(|_| count + 1))
+}
+
+/// Search SunXDCC for each of the given `queries`, in order.
+///
+/// Results are deduplicated across all queries by their filename and filesize,
+/// which makes this useful for synonym searches that would otherwise return the
+/// same file more than once. Errors are passed through as they occur.
+///
+/// ```no_run
+/// # use sunxdcc;
+/// for result in sunxdcc::search_multi(&["breaking bad", "breaking.bad"]) {
+///     println!("{:?}", result.unwrap());
+/// }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn search_multi<'search>(
+    queries: &'search [&'search str],
+) -> impl Iterator<Item = Result<SearchResult, Error>> + 'search {
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+
+    queries
+        .iter()
+        .flat_map(|query| search(query))
+        .filter(move |result| match result {
+            Ok(result) => seen.insert((result.filename.clone(), result.filesize.clone())),
+            Err(_) => true,
+        })
+}
+
+/// Returns the URL of SunXDCC's own search page for `query`, e.g. for
+/// "view on site" links.
+///
+/// This is the page a user would visit in their browser, not the API endpoint
+/// that [`search`](search) queries.
+///
+/// ```
+/// # use sunxdcc;
+/// let url = sunxdcc::search_page_url("breaking bad");
+/// assert_eq!(url.as_str(), "https://sunxdcc.com/?sterm=breaking+bad");
+/// ```
+pub fn search_page_url(query: &str) -> Url {
+    // Unwrap safety: SEARCH_PAGE_URL is a correct URL.
+    #[allow(clippy::unwrap_used)]
+    let mut url = Url::parse(SEARCH_PAGE_URL).unwrap();
+    url.query_pairs_mut().append_pair("sterm", query);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // These tests need only the crate's types, and so run in every build,
+    // including one without any features.
+
+    fn result() -> SearchResult {
+        SearchResult {
+            network: "irc.foo.net:6697".into(),
+            channel: "#Foo, bar".into(),
+            bot: "foobot".into(),
+            filesize: "[1.5G]".into(),
+            filename: "foo.txt".into(),
+            packet_number: "#12".into(),
+            download_count: "5x".into(),
+            upload_speed: Some("1000.25kB/s".into()),
+        }
+    }
+
+    #[test]
+    fn parses_result_fields() {
+        let result = result();
+        assert_eq!(result.network_host(), "irc.foo.net");
+        assert_eq!(result.network_port(), Some(6697));
+        assert_eq!(result.filesize_parts(), Some(("1.5", "G")));
+        assert_eq!(result.download_count_value(), Some(5));
+        assert_eq!(result.packet_number_u64().unwrap(), 12);
+        assert!(result.upload_speed_parsed().is_some());
+        assert_eq!(
+            result.channels(),
+            [Channel::from("#foo"), Channel::from("#bar")]
+        );
+    }
+
+    #[test]
+    fn builds_search_page_urls() {
+        assert_eq!(
+            search_page_url("foo & bar").as_str(),
+            "https://sunxdcc.com/?sterm=foo+%26+bar"
+        );
+        assert_eq!(QueryEncoding::Percent.encode("foo bar"), "foo%20bar");
+    }
+
+    /// Returns a page body with one result for each of the given filenames.
+    #[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
+    fn page(filenames: &[&str]) -> Vec<u8> {
+        let column = |value: &str| vec![value.to_string(); filenames.len()];
+        serde_json::json!({
+            "network": column("irc.foo.net"),
+            "channel": column("#foo"),
+            "bot": column("foobot"),
+            "fsize": column("[1M]"),
+            "fname": filenames,
+            "packnum": column("#1"),
+            "gets": column("0x"),
+            "botrec": column("Na"),
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    /// A `StaticTransport` that records the pages requested of it.
+    #[cfg(feature = "blocking")]
+    #[derive(Debug)]
+    struct CountingTransport {
+        inner: StaticTransport,
+        requested: Arc<Mutex<Vec<usize>>>,
+    }
+
+    #[cfg(feature = "blocking")]
+    impl Transport for CountingTransport {
+        fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error> {
+            let page = url
+                .query_pairs()
+                .find(|(k, _)| k == "page")
+                .and_then(|(_, v)| v.parse().ok())
+                .unwrap();
+            self.requested.lock().unwrap().push(page);
+            self.inner.get_page(url, limit)
+        }
+    }
+
+    /// Returns a search over the given pages, and a log of the pages it
+    /// requests.
+    #[cfg(feature = "blocking")]
+    fn search_pages(pages: Vec<Vec<u8>>) -> (SearchResults, Arc<Mutex<Vec<usize>>>) {
+        let requested = Arc::new(Mutex::new(vec![]));
+        let transport = CountingTransport {
+            inner: StaticTransport::new(pages),
+            requested: requested.clone(),
+        };
+
+        (
+            SearchResults::with_transport(Box::new(transport), "foo"),
+            requested,
+        )
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
+    #[test]
+    fn consumes_raw_results_in_reverse() {
+        let raw: RawResult = serde_json::from_slice(&page(&["a.txt", "b.txt"])).unwrap();
+        let mut results = vec![];
+        raw.consume(&mut results, false).unwrap();
+
+        let filenames: Vec<_> = results.iter().map(|r| r.filename.as_str()).collect();
+        assert_eq!(filenames, ["b.txt", "a.txt"]);
+        assert_eq!(results[0].upload_speed, None);
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
+    #[test]
+    fn consumes_and_trims_raw_results() {
+        let raw = RawResult {
+            network: vec![" irc.foo.net".into()],
+            channel: vec!["#foo ".into()],
+            bot: vec!["foobot".into()],
+            fsize: vec!["[1M]".into()],
+            fname: vec!["\tfoo.txt\n".into()],
+            packnum: vec!["#1".into()],
+            gets: vec!["0x".into()],
+            botrec: vec!["1kB/s ".into()],
+        };
+        let mut results = vec![];
+        raw.consume(&mut results, true).unwrap();
+
+        assert_eq!(&*results[0].network, "irc.foo.net");
+        assert_eq!(&*results[0].channel, "#foo");
+        assert_eq!(results[0].filename, "foo.txt");
+        assert_eq!(results[0].upload_speed.as_deref(), Some("1kB/s"));
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking", feature = "test-util"))]
+    #[test]
+    fn rejects_inconsistent_raw_results() {
+        let mut raw: RawResult = serde_json::from_slice(&page(&["a.txt", "b.txt"])).unwrap();
+        raw.gets.pop();
+
+        let mut results = vec![];
+        assert!(matches!(
+            raw.consume(&mut results, false),
+            Err(Error::Malformed(_))
+        ));
+        assert!(results.is_empty());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn paginates_until_an_empty_page() {
+        let (results, requested) = search_pages(vec![
+            page(&["a.txt", "b.txt"]),
+            page(&["c.txt"]),
+            page(&["d.txt"]),
+        ]);
+
+        let filenames: Vec<_> = results.map(|r| r.unwrap().filename).collect();
+        assert_eq!(filenames, ["a.txt", "b.txt", "c.txt", "d.txt"]);
+        assert_eq!(*requested.lock().unwrap(), [0, 1, 2, 3]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn stays_finished_after_an_empty_page() {
+        let (mut results, requested) = search_pages(vec![page(&["a.txt"])]);
+
+        assert!(results.next().is_some());
+        assert!(results.next().is_none());
+        assert!(results.next().is_none());
+        assert_eq!(results.pages_fetched(), 2);
+        assert_eq!(*requested.lock().unwrap(), [0, 1]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn empty_searches_fetch_one_page() {
+        let (mut results, requested) = search_pages(vec![]);
+
+        assert!(results.next().is_none());
+        assert_eq!(*requested.lock().unwrap(), [0]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn malformed_pages_are_errors() {
+        let (mut results, _) = search_pages(vec![b"<html>".to_vec()]);
+
+        assert!(matches!(results.next(), Some(Err(Error::Malformed(_)))));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn validates_query_lengths() {
+        for query in ["", " \t\n"] {
+            assert_eq!(
+                validate_query(query, DEFAULT_MIN_QUERY_LEN, DEFAULT_MAX_QUERY_LEN),
+                Err(Error::EmptyQuery)
+            );
+        }
+
+        let validate = |query: &str| {
+            validate_query(query, DEFAULT_MIN_QUERY_LEN, DEFAULT_MAX_QUERY_LEN).map_err(|e| {
+                let Error::InvalidQuery { reason } = e else {
+                    panic!("unexpected error: {e:?}");
+                };
+                reason
+            })
+        };
+
+        assert_eq!(
+            validate("a").unwrap_err(),
+            "query is 1 characters long, but must be at least 2"
+        );
+        assert_eq!(
+            validate(&"a".repeat(500)).unwrap_err(),
+            "query is 500 characters long, but must be at most 256"
+        );
+
+        // Lengths are in characters, after trimming.
+        assert!(validate(" ab ").is_ok());
+        assert!(validate(&"é".repeat(256)).is_ok());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn invalid_queries_make_no_requests() {
+        for query in ["", "a", &"a".repeat(500)] {
+            let (mut results, requested) = search_pages(vec![page(&["a.txt"])]);
+            results.reset(query);
+
+            assert!(matches!(
+                results.next(),
+                Some(Err(Error::EmptyQuery | Error::InvalidQuery { .. }))
+            ));
+            assert!(results.next().is_none());
+            assert!(requested.lock().unwrap().is_empty());
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn invalid_queries_stay_invalid_after_restarting() {
+        for query in ["  ", "a"] {
+            let (mut results, requested) = search_pages(vec![page(&["a.txt"])]);
+            results.reset(query);
+            let error = results.next().unwrap().unwrap_err();
+
+            results.restart();
+            assert_eq!(results.next().unwrap().unwrap_err(), error);
+            assert!(results.next().is_none());
+
+            results.skip_pages(1);
+            assert_eq!(results.next().unwrap().unwrap_err(), error);
+            assert!(results.next().is_none());
+
+            assert!(requested.lock().unwrap().is_empty());
+        }
+    }
 }
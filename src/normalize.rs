@@ -0,0 +1,47 @@
+//! Normalization of search queries.
+
+#[cfg(feature = "unicode")]
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Normalize `query` for searching: collapse runs of whitespace into single
+/// spaces, trim it, and lowercase it.
+///
+/// With the `unicode` feature, the query is also put into Unicode Normalization
+/// Form C, so that e.g. a decomposed `e` + `◌́` matches a precomposed `é`.
+///
+/// ```
+/// # use sunxdcc::normalize_query;
+/// assert_eq!(normalize_query("  Amélie   Poulain "), "amélie poulain");
+/// assert_eq!(normalize_query("amelie poulain"), "amelie poulain");
+/// ```
+pub fn normalize_query(query: &str) -> String {
+    #[cfg(feature = "unicode")]
+    let query = &query.nfc().collect::<String>();
+
+    collapse_whitespace(query).to_lowercase()
+}
+
+/// Trim `query`, and collapse its internal runs of whitespace into single
+/// spaces.
+pub(crate) fn collapse_whitespace(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Strip diacritics from `query`, e.g. `é` into `e`.
+///
+/// Only combining marks are removed, so characters without a decomposition
+/// (such as `ø`) are left alone.
+///
+/// ```
+/// # use sunxdcc::strip_diacritics;
+/// assert_eq!(strip_diacritics("Amélie"), "Amelie");
+/// assert_eq!(strip_diacritics("Amelie"), "Amelie");
+/// ```
+#[cfg(feature = "unicode")]
+pub fn strip_diacritics(query: &str) -> String {
+    query
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .nfc()
+        .collect()
+}
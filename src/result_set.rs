@@ -0,0 +1,147 @@
+//! A collection of search results.
+
+use std::cmp;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+
+use crate::{Criterion, SearchResult};
+
+/// A collected set of search results, with conveniences for working with them
+/// as a whole.
+///
+/// A `SearchResultSet` can be collected from any iterator of results, and
+/// dereferences to a slice of them:
+///
+/// ```
+/// # use sunxdcc::{SearchResult, SearchResultSet};
+/// let results: SearchResultSet = Vec::<SearchResult>::new().into_iter().collect();
+/// assert!(results.is_empty());
+/// ```
+///
+/// Collecting a search into a `Result<SearchResultSet, Error>` stops at the
+/// first error:
+///
+/// ```
+/// # use sunxdcc::{Error, SearchResults, SearchResultSet, StaticTransport};
+/// let page = br##"{"network":["irc.foo.net"],"channel":["#foo"],"bot":["foobot"],
+///     "fsize":["[1M]"],"fname":["a.txt"],"packnum":["#1"],"gets":["3x"],
+///     "botrec":["Na"]}"##;
+/// let transport = StaticTransport::new(vec![page.to_vec(), b"garbage".to_vec()]);
+///
+/// let results = SearchResults::with_transport(Box::new(transport), "foo")
+///     .collect::<Result<SearchResultSet, Error>>();
+/// assert!(results.is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchResultSet(Vec<SearchResult>);
+
+impl SearchResultSet {
+    /// Returns the result with the most downloads, if any.
+    ///
+    /// Counts are compared by
+    /// [`download_count_value`](SearchResult::download_count_value), with
+    /// malformed counts ranked below all others; ties are broken in favor of
+    /// the earliest result.
+    pub fn best_by_downloads(&self) -> Option<&SearchResult> {
+        self.0
+            .iter()
+            .rev()
+            .max_by_key(|result| result.download_count_value())
+    }
+
+    /// Returns the results on the given network, compared case-insensitively.
+    pub fn filter_by_network(&self, network: &str) -> SearchResultSet {
+        self.0
+            .iter()
+            .filter(|result| result.network.eq_ignore_ascii_case(network))
+            .cloned()
+            .collect()
+    }
+
+    /// Sort this set's results by `criterion`, best first.
+    ///
+    /// Results whose value is unknown or malformed are sorted last. The sort
+    /// is stable, so ties stay in their original order.
+    pub fn sort_by(&mut self, criterion: Criterion) {
+        self.0
+            .sort_by_key(|result| cmp::Reverse(criterion.value(result)));
+    }
+
+    /// Remove all but the first result for each distinct filename, ignoring
+    /// case.
+    pub fn dedup_by_filename(&mut self) {
+        let mut seen = HashSet::new();
+        self.0
+            .retain(|result| seen.insert(result.filename.to_lowercase()));
+    }
+
+    /// Keep only the results for which `f` returns `true`.
+    pub fn retain<F: FnMut(&SearchResult) -> bool>(&mut self, f: F) {
+        self.0.retain(f);
+    }
+
+    /// Returns the total size of this set's files, in bytes.
+    ///
+    /// Results with malformed filesizes count as zero bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.0
+            .iter()
+            .map(|result| result.filesize_bytes().unwrap_or_default())
+            .sum()
+    }
+
+    /// Consume this set, returning its results.
+    pub fn into_inner(self) -> Vec<SearchResult> {
+        self.0
+    }
+}
+
+impl Deref for SearchResultSet {
+    type Target = [SearchResult];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<SearchResult>> for SearchResultSet {
+    fn from(results: Vec<SearchResult>) -> Self {
+        Self(results)
+    }
+}
+
+impl FromIterator<SearchResult> for SearchResultSet {
+    fn from_iter<I: IntoIterator<Item = SearchResult>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for SearchResultSet {
+    type Item = SearchResult;
+    type IntoIter = std::vec::IntoIter<SearchResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SearchResultSet {
+    type Item = &'a SearchResult;
+    type IntoIter = std::slice::Iter<'a, SearchResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Sets are displayed as a numbered list, one result per line.
+impl fmt::Display for SearchResultSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, result) in self.0.iter().enumerate() {
+            writeln!(f, "{}. {result}", i + 1)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Fixtures for testing code that consumes this crate.
+//!
+//! This module is only available with the `test-util` feature. Its API carries
+//! no stability promises beyond the current minor version.
+
+use crate::{parse_page, Error, SearchResult};
+
+/// Create `n` distinct, plausible-looking search results.
+// The field conversions are identities without the `typed-fields` feature.
+#[allow(clippy::useless_conversion)]
+pub fn sample_results(n: usize) -> Vec<SearchResult> {
+    (0..n)
+        .map(|i| SearchResult {
+            network: "irc.example.net".into(),
+            channel: "#example".into(),
+            bot: format!("bot{}", i % 3).into(),
+            filesize: format!("[{}M]", (i + 1) * 100),
+            filename: format!("file{i}.txt"),
+            packet_number: format!("#{}", i + 1),
+            download_count: format!("{i}x"),
+            upload_speed: (i % 2 == 0).then(|| format!("{}.00kB/s", (i + 1) * 10)),
+        })
+        .collect()
+}
+
+/// Parse search results from a page body in the API's column-oriented format.
+///
+/// This accepts the same JSON as the SunXDCC API returns, so captured responses
+/// can be pasted directly into tests.
+pub fn results_from_json(json: &str) -> Result<Vec<SearchResult>, Error> {
+    parse_page(json.as_bytes())
+}
+
+/// Create an iterator that yields the given results, in the same form as
+/// [`SearchResults`](crate::SearchResults).
+pub fn fake_search(
+    results: Vec<SearchResult>,
+) -> impl Iterator<Item = Result<SearchResult, Error>> {
+    results.into_iter().map(Ok)
+}
@@ -0,0 +1,371 @@
+//! Pluggable HTTP transports for fetching pages of search results.
+
+use std::fmt::Debug;
+#[cfg(feature = "blocking")]
+use std::fs;
+#[cfg(feature = "blocking")]
+use std::io::Read;
+#[cfg(feature = "pinning")]
+use std::iter::zip;
+#[cfg(feature = "blocking")]
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+#[cfg(any(feature = "async", feature = "blocking"))]
+use std::time::SystemTime;
+
+#[cfg(feature = "blocking")]
+use reqwest::header::RETRY_AFTER;
+#[cfg(feature = "blocking")]
+use reqwest::StatusCode;
+#[cfg(feature = "pinning")]
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::Error;
+
+/// The body of a page with no results, as returned by the SunXDCC API.
+pub(crate) const EMPTY_PAGE: &[u8] =
+    br#"{"network":[],"channel":[],"bot":[],"fsize":[],"fname":[],"packnum":[],"gets":[],"botrec":[]}"#;
+
+/// A means of fetching raw pages of search results.
+///
+/// The default transport is a [`reqwest::blocking::Client`]; other
+/// implementations can be supplied via
+/// [`SearchResults::with_transport`](crate::SearchResults::with_transport),
+/// e.g. to embed this crate in an environment with its own HTTP stack.
+pub trait Transport: Debug + Send {
+    /// Fetch the raw body of the page at the given URL.
+    ///
+    /// Implementations must not return bodies longer than `limit` bytes, and
+    /// should produce [`Error::ResponseTooLarge`](Error::ResponseTooLarge)
+    /// instead. Rate-limited requests should produce
+    /// [`Error::RateLimited`](Error::RateLimited), which the caller may retry.
+    fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error>;
+
+    /// Fetch the raw body of the page at the given URL, giving up once
+    /// `timeout` has elapsed.
+    ///
+    /// This is used to keep requests within a search's deadline. The default
+    /// implementation ignores `timeout`, and calls
+    /// [`get_page`](Transport::get_page).
+    fn get_page_with_timeout(
+        &self,
+        url: &Url,
+        limit: usize,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        let _ = timeout;
+        self.get_page(url, limit)
+    }
+}
+
+/// Fetch a page from `transport`, with a timeout if given.
+#[cfg(feature = "blocking")]
+pub(crate) fn get_page(
+    transport: &dyn Transport,
+    url: &Url,
+    limit: usize,
+    timeout: Option<Duration>,
+) -> Result<Vec<u8>, Error> {
+    match timeout {
+        Some(timeout) => transport.get_page_with_timeout(url, limit, timeout),
+        None => transport.get_page(url, limit),
+    }
+}
+
+/// Parse a `Retry-After` header value, which is either a number of seconds
+/// or an HTTP-date.
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    // An HTTP-date in the past means "retry now".
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(feature = "blocking")]
+impl Transport for reqwest::blocking::Client {
+    fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error> {
+        read_page(self.get(url.clone()).send()?, limit)
+    }
+
+    fn get_page_with_timeout(
+        &self,
+        url: &Url,
+        limit: usize,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        read_page(self.get(url.clone()).timeout(timeout).send()?, limit)
+    }
+}
+
+/// Read a page's body from `response`, checking for rate limiting and
+/// enforcing the size `limit`.
+#[cfg(feature = "blocking")]
+fn read_page(response: reqwest::blocking::Response, limit: usize) -> Result<Vec<u8>, Error> {
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+
+        return Err(Error::RateLimited {
+            retry_after: retry_after,
+        });
+    }
+
+    // Read at most one byte past the limit, so that we can tell whether
+    // the limit was actually exceeded without buffering the entire body.
+    let mut body = Vec::new();
+    response
+        .take((limit as u64).saturating_add(1))
+        .read_to_end(&mut body)?;
+
+    if body.len() > limit {
+        return Err(Error::ResponseTooLarge {
+            limit: limit,
+            got_at_least: body.len(),
+        });
+    }
+
+    Ok(body)
+}
+
+/// A transport that discards responses from servers whose certificate doesn't
+/// match one of a set of SHA-256 fingerprints.
+///
+/// This validates each response after the fact: the request has already been
+/// sent by the time the certificate is checked. The client must be built with
+/// TLS info enabled, so that the server's certificate is available.
+#[cfg(feature = "pinning")]
+#[derive(Debug)]
+pub(crate) struct PinnedTransport {
+    /// The underlying HTTP client.
+    pub(crate) client: reqwest::blocking::Client,
+    /// The pinned fingerprints.
+    pub(crate) pins: Vec<[u8; 32]>,
+}
+
+#[cfg(feature = "pinning")]
+impl PinnedTransport {
+    /// Fetch the page at `url`, checking the server's certificate before
+    /// reading its body.
+    fn fetch(&self, url: &Url, limit: usize, timeout: Option<Duration>) -> Result<Vec<u8>, Error> {
+        let mut request = self.client.get(url.clone());
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        let response = request.send()?;
+
+        let fingerprint = response
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(|info| info.peer_certificate())
+            .map(|der| <[u8; 32]>::from(Sha256::digest(der)));
+
+        match fingerprint {
+            Some(fingerprint) if self.pins.contains(&fingerprint) => read_page(response, limit),
+            _ => Err(Error::CertificatePinMismatch {
+                fingerprint: fingerprint
+                    .map(|fingerprint| fingerprint.iter().map(|b| format!("{b:02X}")).collect()),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "pinning")]
+impl Transport for PinnedTransport {
+    fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error> {
+        self.fetch(url, limit, None)
+    }
+
+    fn get_page_with_timeout(
+        &self,
+        url: &Url,
+        limit: usize,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        self.fetch(url, limit, Some(timeout))
+    }
+}
+
+/// Parse a SHA-256 fingerprint given in hex, optionally separated by colons.
+#[cfg(feature = "pinning")]
+pub(crate) fn parse_fingerprint(fingerprint: &str) -> Result<[u8; 32], Error> {
+    let invalid = || Error::InvalidCertificate(format!("invalid fingerprint: {fingerprint:?}"));
+
+    let digits = fingerprint
+        .chars()
+        .filter(|c| *c != ':' && !c.is_whitespace())
+        .map(|c| c.to_digit(16).ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut pin = [0; 32];
+    if digits.len() != pin.len() * 2 {
+        return Err(invalid());
+    }
+
+    for (byte, pair) in zip(pin.iter_mut(), digits.chunks(2)) {
+        *byte = (pair[0] * 16 + pair[1]) as u8;
+    }
+
+    Ok(pin)
+}
+
+/// An in-memory transport that serves canned page bodies.
+///
+/// Each body is served for the page whose index matches its position; requests
+/// for pages past the end receive an empty page, terminating the search.
+///
+/// ```
+/// # use sunxdcc::{SearchResults, StaticTransport};
+/// let page = r##"{"network":["irc.foo.net"],"channel":["#foo"],"bot":["foobot"],
+///     "fsize":["[1M]"],"fname":["foo.txt"],"packnum":["#1"],"gets":["0x"],"botrec":["Na"]}"##;
+/// let transport = StaticTransport::new(vec![page.into()]);
+///
+/// let results = SearchResults::with_transport(Box::new(transport), "foo")
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(results.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StaticTransport {
+    /// The body for each page, in page order.
+    pages: Vec<Vec<u8>>,
+}
+
+impl StaticTransport {
+    /// Create a new `StaticTransport` serving the given page bodies.
+    pub fn new(pages: Vec<Vec<u8>>) -> Self {
+        Self { pages: pages }
+    }
+}
+
+impl Transport for StaticTransport {
+    fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error> {
+        let page = page_number(url)?;
+
+        let body = self.pages.get(page).map_or(EMPTY_PAGE, |p| p.as_slice());
+        if body.len() > limit {
+            return Err(Error::ResponseTooLarge {
+                limit: limit,
+                got_at_least: body.len(),
+            });
+        }
+
+        Ok(body.to_vec())
+    }
+}
+
+/// Returns the recording path for the given page within `dir`.
+#[cfg(feature = "blocking")]
+fn record_path(dir: &Path, url: &Url) -> Result<PathBuf, Error> {
+    let page = page_number(url)?;
+    Ok(dir.join(format!("page-{page}.rec")))
+}
+
+/// Extract the page number from a page URL.
+fn page_number(url: &Url) -> Result<usize, Error> {
+    url.query_pairs()
+        .find(|(k, _)| k == "page")
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .ok_or_else(|| Error::Malformed(format!("missing page number in {url}")))
+}
+
+/// A transport that records every page it fetches to a directory.
+///
+/// Each page is written to `page-N.rec`, consisting of a header with the
+/// request URL and page number, followed by a blank line and the raw body.
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+pub(crate) struct RecordingTransport {
+    /// The transport to record.
+    pub(crate) inner: Box<dyn Transport>,
+    /// The directory to record into.
+    pub(crate) dir: PathBuf,
+}
+
+#[cfg(feature = "blocking")]
+impl RecordingTransport {
+    /// Fetch the page at `url` from our inner transport, and record it.
+    fn fetch(&self, url: &Url, limit: usize, timeout: Option<Duration>) -> Result<Vec<u8>, Error> {
+        let body = get_page(&*self.inner, url, limit, timeout)?;
+
+        let mut record = format!("url: {url}\npage: {}\n\n", page_number(url)?).into_bytes();
+        record.extend_from_slice(&body);
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(record_path(&self.dir, url)?, record)?;
+
+        Ok(body)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Transport for RecordingTransport {
+    fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error> {
+        self.fetch(url, limit, None)
+    }
+
+    fn get_page_with_timeout(
+        &self,
+        url: &Url,
+        limit: usize,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        self.fetch(url, limit, Some(timeout))
+    }
+}
+
+/// A transport that serves pages previously recorded by a `RecordingTransport`.
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+pub(crate) struct ReplayTransport {
+    /// The directory to replay from.
+    pub(crate) dir: PathBuf,
+}
+
+#[cfg(feature = "blocking")]
+impl Transport for ReplayTransport {
+    fn get_page(&self, url: &Url, limit: usize) -> Result<Vec<u8>, Error> {
+        let record = fs::read(record_path(&self.dir, url)?)?;
+
+        let split = record
+            .windows(2)
+            .position(|w| w == b"\n\n")
+            .ok_or_else(|| Error::Malformed("recording is missing its header".into()))?;
+        let (header, body) = (&record[..split], &record[split + 2..]);
+
+        let recorded_url = String::from_utf8_lossy(header)
+            .lines()
+            .find_map(|line| line.strip_prefix("url: ").map(Url::parse))
+            .ok_or_else(|| Error::Malformed("recording is missing its URL".into()))?
+            .map_err(|e| Error::Malformed(format!("recording has an invalid URL: {e}")))?;
+
+        // Only the query is compared, so that recordings made against one host
+        // can be replayed for another.
+        if recorded_url.query() != url.query() {
+            return Err(Error::Malformed(format!(
+                "recording mismatch: expected a page for {url}, found {recorded_url}"
+            )));
+        }
+
+        if body.len() > limit {
+            return Err(Error::ResponseTooLarge {
+                limit: limit,
+                got_at_least: body.len(),
+            });
+        }
+
+        Ok(body.to_vec())
+    }
+}
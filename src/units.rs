@@ -0,0 +1,252 @@
+//! Typed representations of the unit-bearing fields in search results.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+
+/// The size units used by SunXDCC, in ascending order.
+const SIZE_UNITS: &[(&str, u64)] = &[
+    ("B", 1),
+    ("K", 1 << 10),
+    ("M", 1 << 20),
+    ("G", 1 << 30),
+    ("T", 1 << 40),
+];
+
+/// The most decimal places that a displayed size is given, before falling back
+/// to a whole number of bytes.
+const MAX_SIZE_DECIMALS: usize = 3;
+
+/// Format `value` with at most `decimals` decimal places, omitting trailing
+/// zeros.
+fn format_decimal(value: f64, decimals: usize) -> String {
+    let formatted = format!("{value:.decimals$}");
+    match formatted.contains('.') {
+        true => formatted.trim_end_matches('0').trim_end_matches('.').into(),
+        false => formatted,
+    }
+}
+
+/// A file size, in bytes.
+///
+/// Sizes are parsed from and displayed in the API's format, e.g. `"123M"` or
+/// `"4.5G"`, with binary (1024-based) units. A size is displayed in the largest
+/// unit that fits, with as few decimal places as parse back to exactly the
+/// same size, or as a whole number of bytes if no short form does. Displaying
+/// and re-parsing a size therefore always produces the same size:
+///
+/// ```
+/// # use sunxdcc::FileSize;
+/// assert_eq!(FileSize(1536 << 20).to_string(), "1.5G");
+/// assert_eq!(FileSize(1342177280).to_string(), "1.25G");
+/// assert_eq!(FileSize(1000000).to_string(), "1000000B");
+/// ```
+///
+/// Sizes are ordered by their number of bytes, so sizes written in different
+/// units compare as expected, and equal sizes are equal regardless of unit:
+///
+/// ```
+/// # use sunxdcc::FileSize;
+/// let size = |s: &str| s.parse::<FileSize>().unwrap();
+/// assert!(size("999K") < size("1M"));
+/// assert!(size("1M") < size("1.5M"));
+/// assert!(size("1.5M") < size("1G"));
+/// assert_eq!(size("1024K"), size("1M"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileSize(pub u64);
+
+impl FileSize {
+    /// Returns this size in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for FileSize {
+    type Err = Error;
+
+    /// Parse a size like `"123M"`, optionally bracketed as in `"[123M]"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::Malformed(format!("invalid file size: {s:?}"));
+
+        let inner = s.trim();
+        let inner = inner
+            .strip_prefix('[')
+            .and_then(|i| i.strip_suffix(']'))
+            .unwrap_or(inner);
+
+        let split = inner
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(inner.len());
+        let (value, unit) = inner.split_at(split);
+
+        let multiplier = match unit.to_ascii_uppercase().as_str() {
+            "" => 1,
+            unit => SIZE_UNITS
+                .iter()
+                .find(|(u, _)| {
+                    unit.strip_prefix(*u)
+                        .is_some_and(|r| r.is_empty() || r == "B")
+                })
+                .map(|(_, m)| *m)
+                .ok_or_else(malformed)?,
+        };
+
+        // Whole numbers are parsed exactly, even beyond `f64`'s precision.
+        if let Ok(whole) = value.parse::<u64>() {
+            return whole
+                .checked_mul(multiplier)
+                .map(Self)
+                .ok_or_else(malformed);
+        }
+
+        let value: f64 = value.parse().map_err(|_| malformed())?;
+        Ok(Self((value * multiplier as f64).round() as u64))
+    }
+}
+
+impl fmt::Display for FileSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (unit, multiplier) = SIZE_UNITS
+            .iter()
+            .rev()
+            .find(|(_, m)| self.0 >= *m)
+            .unwrap_or(&SIZE_UNITS[0]);
+
+        let value = self.0 as f64 / *multiplier as f64;
+        let short = (1..=MAX_SIZE_DECIMALS)
+            .map(|decimals| format!("{}{unit}", format_decimal(value, decimals)))
+            .find(|short| short.parse() == Ok(*self));
+
+        match short {
+            Some(short) => f.write_str(&short),
+            None => write!(f, "{}B", self.0),
+        }
+    }
+}
+
+/// A bot's upload speed, in bytes per second.
+///
+/// Speeds are parsed from and displayed in the API's format, e.g.
+/// `"1000.25kB/s"`, where a kilobyte is 1000 bytes. Either `kB/s` or `KB/s`
+/// is accepted when parsing.
+///
+/// Speeds are stored as a whole number of bytes per second, rounding any
+/// fractional bytes, so unlike the API's decimal representation they have a
+/// total order:
+///
+/// ```
+/// # use sunxdcc::UploadSpeed;
+/// let speed = |s: &str| s.parse::<UploadSpeed>().unwrap();
+/// assert!(speed("999.99kB/s") < speed("1000kB/s"));
+/// assert_eq!(speed("1000.0kB/s"), speed("1000KB/s"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UploadSpeed(pub u64);
+
+impl UploadSpeed {
+    /// Returns this speed in bytes per second.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for UploadSpeed {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::Malformed(format!("invalid upload speed: {s:?}"));
+
+        let s = s.trim();
+        let value = s
+            .strip_suffix("kB/s")
+            .or_else(|| s.strip_suffix("KB/s"))
+            .ok_or_else(malformed)?;
+        let value: f64 = value.trim().parse().map_err(|_| malformed())?;
+
+        if !value.is_finite() || value < 0.0 {
+            return Err(malformed());
+        }
+
+        Ok(Self((value * 1000.0).round() as u64))
+    }
+}
+
+impl fmt::Display for UploadSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2}kB/s", self.0 as f64 / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchResult;
+
+    fn result(filesize: &str) -> SearchResult {
+        SearchResult {
+            network: "irc.foo.net".into(),
+            channel: "#foo".into(),
+            bot: "foobot".into(),
+            filesize: filesize.into(),
+            filename: "foo.txt".into(),
+            packet_number: "#1".into(),
+            download_count: "0x".into(),
+            upload_speed: None,
+        }
+    }
+
+    #[test]
+    fn arbitrary_file_sizes_round_trip() {
+        let mut sizes = vec![0, 1, 1023, 1024, 1048575, 1000000, (1 << 53) + 1, u64::MAX];
+
+        // A xorshift generator, scaled down to cover every unit.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        for i in 0..10_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            sizes.push(state >> (i % 64));
+        }
+
+        for bytes in sizes {
+            let displayed = FileSize(bytes).to_string();
+            assert_eq!(displayed.parse(), Ok(FileSize(bytes)), "{displayed}");
+        }
+    }
+
+    #[test]
+    fn file_sizes_round_trip() {
+        for s in ["0", "512", "1K", "1.5K", "123M", "4.5G", "1.2T", "1024T"] {
+            let size: FileSize = s.parse().unwrap();
+            assert_eq!(size.to_string().parse::<FileSize>().unwrap(), size, "{s}");
+        }
+        assert_eq!("1024K".parse::<FileSize>().unwrap().to_string(), "1M");
+        assert_eq!("[4.50G]".parse::<FileSize>().unwrap().to_string(), "4.5G");
+    }
+
+    #[test]
+    fn parsed_file_sizes_match_from_str() {
+        for s in [
+            "[123M]", "[4.5G]", "[1K]", "[0B]", "[1.2T]", " [7MB] ", "123M",
+        ] {
+            let result = result(s);
+            let size = s.parse::<FileSize>().unwrap();
+            assert_eq!(result.filesize_parsed(), Some(size), "{s}");
+            assert_eq!(result.filesize_bytes(), Some(size.bytes()), "{s}");
+            assert_eq!(
+                result.filesize_parsed().unwrap().to_string(),
+                size.to_string()
+            );
+        }
+
+        for s in ["", "[]", "[M]", "[1Q]", "[1.2.3G]", "[-1M]"] {
+            let result = result(s);
+            assert!(s.parse::<FileSize>().is_err(), "{s}");
+            assert_eq!(result.filesize_parsed(), None, "{s}");
+            assert_eq!(result.filesize_bytes(), None, "{s}");
+        }
+    }
+}
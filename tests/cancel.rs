@@ -0,0 +1,63 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{page, MockServer, Response};
+use sunxdcc::SearchClient;
+
+fn client(server: &MockServer) -> SearchClient {
+    SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn cancelling_between_pages_stops_requests() {
+    let server = MockServer::pages(vec![
+        page(&["a.txt", "b.txt"]),
+        page(&["c.txt"]),
+        page(&["d.txt"]),
+    ]);
+    let (mut results, cancel) = client(&server).search("foo").cancellable();
+
+    let (fetched_tx, fetched_rx) = mpsc::channel();
+    let (resume_tx, resume_rx) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        let first = results.next().unwrap().unwrap().filename;
+        fetched_tx.send(()).unwrap();
+        resume_rx.recv().unwrap();
+
+        (first, results.count())
+    });
+
+    fetched_rx.recv().unwrap();
+    cancel.cancel();
+    resume_tx.send(()).unwrap();
+
+    let (first, rest) = worker.join().unwrap();
+    assert_eq!(first, "a.txt");
+    assert_eq!(rest, 0);
+    assert_eq!(server.requested_pages(), [0]);
+}
+
+#[test]
+fn cancelling_cuts_retry_waits_short() {
+    let server =
+        MockServer::start(|_| Response::status(429, "slow down").header("Retry-After", "30"));
+    let (results, cancel) = client(&server).search("foo").cancellable();
+    let results = results.retry_budget(5);
+
+    let start = Instant::now();
+    let worker = thread::spawn(move || results.filter(Result::is_ok).count());
+    thread::sleep(Duration::from_millis(200));
+    cancel.cancel();
+
+    assert_eq!(worker.join().unwrap(), 0);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(server.request_count(), 1);
+}
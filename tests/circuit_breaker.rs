@@ -0,0 +1,91 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::{Error, SearchClient};
+
+const COOLDOWN: Duration = Duration::from_millis(300);
+
+/// Start a server that fails every request until it's marked healthy.
+fn server() -> (MockServer, Arc<AtomicBool>) {
+    let healthy = Arc::new(AtomicBool::new(false));
+    let server = {
+        let healthy = healthy.clone();
+        MockServer::start(
+            move |request| match (healthy.load(Ordering::SeqCst), request.page()) {
+                (false, _) => Response::status(500, "internal server error"),
+                (true, 0) => Response::ok(page(&["a.txt"])),
+                (true, _) => Response::ok(empty_page()),
+            },
+        )
+    };
+
+    (server, healthy)
+}
+
+fn client(server: &MockServer) -> SearchClient {
+    SearchClient::builder()
+        .base_url(server.url())
+        .circuit_breaker(2, COOLDOWN)
+        .build()
+        .unwrap()
+}
+
+fn first(client: &SearchClient) -> Result<(), Error> {
+    client.search("foo").next().unwrap().map(|_| ())
+}
+
+#[test]
+fn trips_and_fails_fast() {
+    let (server, _) = server();
+    let client = client(&server);
+
+    assert!(matches!(first(&client), Err(Error::Malformed(_))));
+    assert!(matches!(first(&client), Err(Error::Malformed(_))));
+
+    // Tripped: no further requests are made.
+    assert!(matches!(first(&client), Err(Error::CircuitOpen { .. })));
+    assert!(matches!(first(&client), Err(Error::CircuitOpen { .. })));
+    assert_eq!(server.request_count(), 2);
+}
+
+#[test]
+fn recovers_after_the_cooldown() {
+    let (server, healthy) = server();
+    let client = client(&server);
+
+    for _ in 0..2 {
+        assert!(first(&client).is_err());
+    }
+    assert!(matches!(first(&client), Err(Error::CircuitOpen { .. })));
+
+    healthy.store(true, Ordering::SeqCst);
+    thread::sleep(COOLDOWN);
+
+    // The probe succeeds, closing the breaker for good.
+    let results: Vec<_> = client.search("foo").collect();
+    assert_eq!(results.len(), 1);
+    assert!(first(&client).is_ok());
+    assert_eq!(server.request_count(), 2 + 2 + 1);
+}
+
+#[test]
+fn failed_probes_reopen_the_breaker() {
+    let (server, _) = server();
+    let client = client(&server);
+
+    for _ in 0..2 {
+        assert!(first(&client).is_err());
+    }
+    thread::sleep(COOLDOWN);
+
+    assert!(matches!(first(&client), Err(Error::Malformed(_))));
+    assert!(matches!(first(&client), Err(Error::CircuitOpen { .. })));
+    assert_eq!(server.request_count(), 3);
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use assert_cmd::Command;
+use common::{page, MockServer, Response};
+use serde_json::Value;
+
+fn server() -> MockServer {
+    MockServer::pages(vec![
+        page(&["a.txt", "b.txt"]),
+        page(&["c.txt"]),
+        page(&["d.txt"]),
+    ])
+}
+
+/// Returns a command that searches the given server.
+fn sunxdcc(server: &MockServer) -> Command {
+    let mut cmd = Command::cargo_bin("sunxdcc").unwrap();
+    cmd.args(["--base-url", server.url()]);
+    cmd
+}
+
+/// Run `cmd`, returning its standard output.
+fn stdout(cmd: &mut Command) -> String {
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn prints_results() {
+    let server = server();
+    let out = stdout(sunxdcc(&server).args(["search", "foo"]));
+
+    assert_eq!(out.lines().count(), 4);
+    assert!(out.lines().next().unwrap().contains("a.txt"));
+    assert_eq!(server.requests()[0].param("sterm").unwrap(), "foo");
+}
+
+#[test]
+fn prints_selected_fields() {
+    let server = server();
+    let out = stdout(sunxdcc(&server).args(["search", "foo", "--fields", "fname,bot"]));
+
+    assert_eq!(out.lines().next().unwrap(), "a.txt\tfoobot");
+}
+
+#[test]
+fn limits_results() {
+    let server = server();
+    let out = stdout(sunxdcc(&server).args(["search", "foo", "--limit", "3"]));
+
+    assert_eq!(out.lines().count(), 3);
+    assert_eq!(server.requested_pages(), [0, 1]);
+}
+
+#[test]
+fn limits_pages() {
+    let server = server();
+    let out = stdout(sunxdcc(&server).args(["search", "foo", "--pages", "2"]));
+
+    assert_eq!(out.lines().count(), 3);
+    assert_eq!(server.requested_pages(), [0, 1]);
+}
+
+#[test]
+fn reports_errors() {
+    let server = MockServer::start(|_| Response::status(500, "internal server error"));
+
+    let assert = sunxdcc(&server).args(["search", "foo"]).assert().failure();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.starts_with("error: malformed response"));
+    assert!(assert.get_output().stdout.is_empty());
+}
+
+#[test]
+fn prints_json() {
+    let server = server();
+    let out = stdout(sunxdcc(&server).args(["search", "foo", "--output", "json"]));
+
+    let Value::Array(results) = serde_json::from_str(&out).unwrap() else {
+        panic!("expected a JSON array");
+    };
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0]["filename"], "a.txt");
+    assert_eq!(results[0]["bot"], "foobot");
+    assert_eq!(results[0]["upload_speed"], Value::Null);
+}
+
+#[test]
+fn prints_json_with_selected_fields() {
+    let server = server();
+    let out = stdout(sunxdcc(&server).args([
+        "search",
+        "foo",
+        "--output",
+        "json",
+        "--fields",
+        "fname,bot",
+    ]));
+
+    let results: Vec<serde_json::Map<String, Value>> = serde_json::from_str(&out).unwrap();
+    let keys: Vec<_> = results[0].keys().map(String::as_str).collect();
+    assert_eq!(keys, ["bot", "filename"]);
+}
+
+#[test]
+fn prints_ndjson() {
+    let server = server();
+    let out =
+        stdout(sunxdcc(&server).args(["search", "foo", "--output", "ndjson", "--limit", "3"]));
+
+    let filenames: Vec<_> = out
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["filename"].clone())
+        .collect();
+    assert_eq!(filenames, ["a.txt", "b.txt", "c.txt"]);
+}
+
+#[test]
+fn prints_valid_json_on_errors() {
+    let server = MockServer::start(|request| match request.page() {
+        0 => Response::ok(page(&["a.txt"])),
+        _ => Response::status(500, "internal server error"),
+    });
+    let assert = sunxdcc(&server)
+        .args(["search", "foo", "--output", "json"])
+        .assert()
+        .failure();
+
+    let results: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(!assert.get_output().stderr.is_empty());
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::{CollectOptions, Error, ErrorPolicy, SearchClient};
+
+/// Start a server whose second page (of three) fails.
+fn flaky_server() -> MockServer {
+    MockServer::start(|request| match request.page() {
+        0 => Response::ok(page(&["a.txt", "b.txt"])),
+        1 => Response::status(500, "internal server error"),
+        2 => Response::ok(page(&["c.txt"])),
+        _ => Response::ok(empty_page()),
+    })
+}
+
+fn collect(
+    server: &MockServer,
+    options: CollectOptions,
+) -> Result<Vec<String>, (Vec<String>, Error)> {
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    let filenames = |results: Vec<sunxdcc::SearchResult>| {
+        results
+            .into_iter()
+            .map(|result| result.filename)
+            .collect::<Vec<_>>()
+    };
+
+    client
+        .search("foo")
+        .collect_all(options)
+        .map(filenames)
+        .map_err(|e| {
+            let (partial, source) = e.into_parts();
+            (filenames(partial), source)
+        })
+}
+
+#[test]
+fn fail_fast_discards_partial_results() {
+    let server = flaky_server();
+    let options = CollectOptions::new().error_policy(ErrorPolicy::FailFast);
+
+    let (partial, source) = collect(&server, options).unwrap_err();
+    assert!(partial.is_empty());
+    assert!(matches!(source, Error::Malformed(_)));
+    assert_eq!(server.requested_pages(), [0, 1]);
+}
+
+#[test]
+fn keep_partial_returns_results_before_the_error() {
+    let server = flaky_server();
+    let options = CollectOptions::new().error_policy(ErrorPolicy::KeepPartial);
+
+    let (partial, source) = collect(&server, options).unwrap_err();
+    assert_eq!(partial, ["a.txt", "b.txt"]);
+    assert!(matches!(source, Error::Malformed(_)));
+    assert_eq!(server.requested_pages(), [0, 1]);
+}
+
+#[test]
+fn skip_failed_pages_continues_past_the_error() {
+    let server = flaky_server();
+    let options = CollectOptions::new().error_policy(ErrorPolicy::SkipFailedPages);
+
+    let results = collect(&server, options).unwrap();
+    assert_eq!(results, ["a.txt", "b.txt", "c.txt"]);
+    assert_eq!(server.requested_pages(), [0, 1, 2, 3]);
+}
+
+#[test]
+fn skip_failed_pages_counts_failures_towards_the_page_limit() {
+    let server = MockServer::start(|_| Response::status(500, "internal server error"));
+    let options = CollectOptions::new()
+        .max_pages(3)
+        .error_policy(ErrorPolicy::SkipFailedPages);
+
+    assert_eq!(collect(&server, options).unwrap(), Vec::<String>::new());
+    assert_eq!(server.requested_pages(), [0, 1, 2]);
+}
+
+#[test]
+fn skip_failed_pages_stops_at_the_default_page_limit() {
+    let server = MockServer::start(|_| Response::status(500, "internal server error"));
+    let options = CollectOptions::new().error_policy(ErrorPolicy::SkipFailedPages);
+
+    assert_eq!(collect(&server, options).unwrap(), Vec::<String>::new());
+    assert_eq!(server.request_count(), 100);
+}
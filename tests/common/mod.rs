@@ -0,0 +1,252 @@
+//! A minimal HTTP server for exercising searches against scripted responses.
+//!
+//! Each test file only uses some of these helpers.
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A request received by a `MockServer`.
+#[derive(Clone, Debug)]
+pub struct Request {
+    /// The request's path, without its query.
+    pub path: String,
+    /// The request's raw query string.
+    pub query: String,
+    /// The request's headers, with lowercased names.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// Returns the (decoded) value of the given query parameter.
+    pub fn param(&self, name: &str) -> Option<String> {
+        url::form_urlencoded::parse(self.query.as_bytes())
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+    }
+
+    /// Returns the requested page number.
+    pub fn page(&self) -> usize {
+        self.param("page")
+            .and_then(|page| page.parse().ok())
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Returns the value of the given header, if present.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// How a `Response` delivers its body.
+#[derive(Clone, Debug)]
+enum Body {
+    /// A body with a `Content-Length`.
+    Fixed(Vec<u8>),
+    /// A chunked body of the given length, without a `Content-Length`.
+    Streamed(usize),
+}
+
+/// A scripted response.
+#[derive(Clone, Debug)]
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Body,
+    delay: Duration,
+}
+
+impl Response {
+    /// A `200 OK` response with the given body.
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::status(200, body)
+    }
+
+    /// A response with the given status and body.
+    pub fn status(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: Body::Fixed(body.into()),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// A `200 OK` response that streams `len` bytes of filler in chunks,
+    /// without announcing its length.
+    pub fn streamed(len: usize) -> Self {
+        Self {
+            body: Body::Streamed(len),
+            ..Self::ok("")
+        }
+    }
+
+    /// Add a header to this response.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Wait for `delay` before responding.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+
+/// An HTTP server on a local port that answers each request with the
+/// response chosen by its handler, and records every request it receives.
+pub struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    /// Start a server that answers requests with `handler`.
+    pub fn start(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/deliver.php", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (log, handler) = (log.clone(), handler.clone());
+                thread::spawn(move || serve(stream, &log, &*handler));
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Start a server that serves the given page bodies in order, followed by
+    /// empty pages.
+    pub fn pages(pages: Vec<String>) -> Self {
+        Self::start(move |request| {
+            Response::ok(
+                pages
+                    .get(request.page())
+                    .cloned()
+                    .unwrap_or_else(empty_page),
+            )
+        })
+    }
+
+    /// Returns the server's API endpoint.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns every request received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns the number of requests received so far.
+    pub fn request_count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Returns the page numbers requested so far, in order.
+    pub fn requested_pages(&self) -> Vec<usize> {
+        self.requests().iter().map(Request::page).collect()
+    }
+}
+
+/// Read a single request from `stream`, and answer it.
+fn serve(mut stream: TcpStream, log: &Mutex<Vec<Request>>, handler: &Handler) {
+    let Some(request) = read_request(&stream) else {
+        return;
+    };
+    log.lock().unwrap().push(request.clone());
+
+    let response = handler(&request);
+    thread::sleep(response.delay);
+
+    let mut head = format!("HTTP/1.1 {} Mock\r\nConnection: close\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+
+    // Errors mean that the client hung up, which some tests do on purpose.
+    let _ = match response.body {
+        Body::Fixed(body) => {
+            head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+            stream
+                .write_all(head.as_bytes())
+                .and_then(|()| stream.write_all(&body))
+        }
+        Body::Streamed(len) => {
+            head.push_str("Transfer-Encoding: chunked\r\n\r\n");
+            stream.write_all(head.as_bytes()).and_then(|()| {
+                let chunk = [b'x'; 8192];
+                let mut remaining = len;
+                while remaining > 0 {
+                    let n = remaining.min(chunk.len());
+                    write!(stream, "{n:x}\r\n")?;
+                    stream.write_all(&chunk[..n])?;
+                    stream.write_all(b"\r\n")?;
+                    remaining -= n;
+                }
+                stream.write_all(b"0\r\n\r\n")
+            })
+        }
+    };
+}
+
+/// Read a request's head from `stream`.
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let target = request_line.split_whitespace().nth(1)?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        match line.trim_end().split_once(':') {
+            Some((name, value)) => headers.push((name.to_lowercase(), value.trim().into())),
+            None => break,
+        }
+    }
+
+    Some(Request {
+        path: path.into(),
+        query: query.into(),
+        headers,
+    })
+}
+
+/// Returns a page body with one result for each of the given filenames.
+pub fn page(filenames: &[&str]) -> String {
+    let column = |value: &str| serde_json::to_string(&vec![value; filenames.len()]).unwrap();
+
+    format!(
+        r#"{{"network":{},"channel":{},"bot":{},"fsize":{},"fname":{},"packnum":{},"gets":{},"botrec":{}}}"#,
+        column("irc.foo.net"),
+        column("#foo"),
+        column("foobot"),
+        column("[1M]"),
+        serde_json::to_string(filenames).unwrap(),
+        column("#1"),
+        column("0x"),
+        column("Na"),
+    )
+}
+
+/// Returns a page body with no results, which ends a search.
+pub fn empty_page() -> String {
+    page(&[])
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "cookies")]
+
+mod common;
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::SearchClient;
+
+#[test]
+fn cookies_persist_across_pages() {
+    let server = MockServer::start(|request| match request.page() {
+        0 => Response::ok(page(&["a.txt"])).header("Set-Cookie", "session=abc123; Path=/"),
+        1 => Response::ok(page(&["b.txt"])),
+        _ => Response::ok(empty_page()),
+    });
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    assert_eq!(client.search("foo").count(), 2);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("cookie"), None);
+    assert_eq!(requests[1].header("cookie"), Some("session=abc123"));
+    assert_eq!(requests[2].header("cookie"), Some("session=abc123"));
+}
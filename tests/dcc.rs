@@ -0,0 +1,125 @@
+#![cfg(feature = "dcc")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use sunxdcc::dcc::{self, DccError, DownloadOptions};
+use sunxdcc::irc::RequestOptions;
+use sunxdcc::SearchResult;
+
+fn result() -> SearchResult {
+    SearchResult {
+        network: "127.0.0.1".into(),
+        channel: "#foo".into(),
+        bot: "foobot".into(),
+        filesize: "[1K]".into(),
+        filename: "foo.txt".into(),
+        packet_number: "#12".into(),
+        download_count: "0x".into(),
+        upload_speed: None,
+    }
+}
+
+/// Start a bot that sends `payload` to whoever connects, until they hang up.
+fn sender(payload: Vec<u8>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        // The client may hang up before reading everything.
+        let _ = stream.write_all(&payload);
+        let _ = stream.read_to_end(&mut vec![]);
+    });
+
+    port
+}
+
+/// Start a fake IRC server that answers the pack request with `offer`,
+/// returning its port.
+fn fake_server(offer: String) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            let reply = match line.split(' ').next().unwrap() {
+                "USER" => ":irc.foo.net 001 arthurdent :Welcome\r\n".to_string(),
+                "JOIN" => format!(":arthurdent!~a@localhost JOIN {}\r\n", &line[5..]),
+                "PRIVMSG" => {
+                    format!(":foobot!~b@localhost PRIVMSG arthurdent :\x01DCC SEND {offer}\x01\r\n")
+                }
+                _ => String::new(),
+            };
+            let _ = writer.write_all(reply.as_bytes());
+        }
+    });
+
+    port
+}
+
+fn options(port: u16) -> DownloadOptions {
+    let request = RequestOptions::new("arthurdent")
+        .port(port)
+        .timeout(Duration::from_secs(5));
+    DownloadOptions::new(request).offer_timeout(Duration::from_secs(5))
+}
+
+#[test]
+fn stops_at_the_offered_size() {
+    let bot = sender(vec![b'x'; 100]);
+    let server = fake_server(format!("foo.txt 2130706433 {bot} 64"));
+    let dir = tempfile::tempdir().unwrap();
+
+    let path = dcc::download(
+        &result(),
+        dir.path(),
+        options(server).allow_private_addresses(true),
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::read(path).unwrap(), [b'x'; 64]);
+}
+
+#[test]
+fn refuses_private_addresses_by_default() {
+    let bot = sender(vec![b'x'; 64]);
+    let server = fake_server(format!("foo.txt 2130706433 {bot} 64"));
+    let dir = tempfile::tempdir().unwrap();
+
+    let err = dcc::download(&result(), dir.path(), options(server)).unwrap_err();
+
+    assert!(matches!(err, DccError::PrivateAddress(addr) if addr.port() == bot));
+    assert!(!dir.path().join("foo.txt").exists());
+}
+
+#[test]
+fn limits_the_file_size() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // Offers with sizes are refused up front...
+    let server = fake_server("foo.txt 2130706433 5000 100".into());
+    let err = dcc::download(
+        &result(),
+        dir.path(),
+        options(server).allow_private_addresses(true).max_size(10),
+    )
+    .unwrap_err();
+    assert!(matches!(err, DccError::TooLarge { max: 10 }));
+
+    // ...while offers without sizes are cut off.
+    let bot = sender(vec![b'x'; 100]);
+    let server = fake_server(format!("foo.txt 2130706433 {bot}"));
+    let err = dcc::download(
+        &result(),
+        dir.path(),
+        options(server).allow_private_addresses(true).max_size(10),
+    )
+    .unwrap_err();
+    assert!(matches!(err, DccError::TooLarge { max: 10 }));
+}
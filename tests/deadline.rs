@@ -0,0 +1,50 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{page, MockServer, Response};
+use sunxdcc::SearchClient;
+
+#[test]
+fn deadline_bounds_requests_in_flight() {
+    let server = MockServer::start(|request| {
+        Response::ok(page(&[&format!("p{}", request.page())])).delay(Duration::from_millis(300))
+    });
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .deadline(Duration::from_millis(500))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let mut results = client.search("foo");
+    let filenames = results
+        .by_ref()
+        .map(|result| result.unwrap().filename)
+        .collect::<Vec<_>>();
+
+    assert_eq!(filenames, ["p0"]);
+    assert!(results.was_truncated());
+    // The second page was abandoned at the deadline, rather than awaited.
+    assert!(start.elapsed() < Duration::from_millis(590));
+    assert_eq!(server.requested_pages(), [0, 1]);
+}
+
+#[test]
+fn timeout_still_applies_within_deadline() {
+    let server =
+        MockServer::start(|_| Response::ok(page(&["slow"])).delay(Duration::from_millis(500)));
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .deadline(Duration::from_secs(10))
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let mut results = client.search("foo");
+    assert!(matches!(results.next(), Some(Err(sunxdcc::Error::Request(e))) if e.is_timeout()));
+    assert!(start.elapsed() < Duration::from_millis(400));
+}
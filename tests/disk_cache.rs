@@ -0,0 +1,79 @@
+#![cfg(feature = "disk-cache")]
+
+mod common;
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::{SearchClient, SearchResult};
+
+const TTL: Duration = Duration::from_secs(3600);
+
+fn server() -> MockServer {
+    MockServer::pages(vec![page(&["a.txt", "b.txt"]), page(&["c.txt"])])
+}
+
+fn search(url: &str, dir: &std::path::Path) -> Result<Vec<SearchResult>, sunxdcc::Error> {
+    SearchClient::builder()
+        .base_url(url)
+        .disk_cache(dir, TTL)
+        .build()
+        .unwrap()
+        .search("foo")
+        .collect()
+}
+
+#[test]
+fn cached_searches_work_offline() {
+    let dir = tempfile::tempdir().unwrap();
+    let online = Arc::new(AtomicBool::new(true));
+    let server = {
+        let online = online.clone();
+        MockServer::start(
+            move |request| match (online.load(Ordering::SeqCst), request.page()) {
+                (false, _) => Response::status(503, "offline"),
+                (true, 0) => Response::ok(page(&["a.txt", "b.txt"])),
+                (true, 1) => Response::ok(page(&["c.txt"])),
+                (true, _) => Response::ok(empty_page()),
+            },
+        )
+    };
+
+    let first = search(server.url(), dir.path()).unwrap();
+    assert_eq!(first.len(), 3);
+    assert_eq!(server.request_count(), 3);
+
+    online.store(false, Ordering::SeqCst);
+    assert_eq!(search(server.url(), dir.path()).unwrap(), first);
+    assert_eq!(server.request_count(), 3);
+}
+
+#[test]
+fn truncated_entries_are_refetched() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = server();
+    let first = search(server.url(), dir.path()).unwrap();
+
+    for entry in fs::read_dir(dir.path()).unwrap() {
+        let path = entry.unwrap().path();
+        let contents = fs::read(&path).unwrap();
+        fs::write(&path, &contents[..contents.len() - 5]).unwrap();
+    }
+
+    assert_eq!(search(server.url(), dir.path()).unwrap(), first);
+    assert_eq!(server.request_count(), 6);
+}
+
+#[test]
+fn unwritable_cache_does_not_fail_searches() {
+    let dir = tempfile::tempdir().unwrap();
+    // A file where the cache directory should be makes every write fail.
+    let blocked = dir.path().join("cache");
+    fs::write(&blocked, "not a directory").unwrap();
+
+    let server = server();
+    assert_eq!(search(server.url(), &blocked).unwrap().len(), 3);
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::SearchClient;
+
+/// Start a server whose second page is empty the first time it's requested.
+fn flaky_server() -> MockServer {
+    let flaked = AtomicBool::new(false);
+    MockServer::start(move |request| match request.page() {
+        0 => Response::ok(page(&["a.txt"])),
+        1 if !flaked.swap(true, Ordering::SeqCst) => Response::ok(empty_page()),
+        1 => Response::ok(page(&["b.txt"])),
+        _ => Response::ok(empty_page()),
+    })
+}
+
+fn filenames(server: &MockServer, tolerance: u32) -> Vec<String> {
+    SearchClient::builder()
+        .base_url(server.url())
+        .empty_page_tolerance(tolerance)
+        .build()
+        .unwrap()
+        .search("foo")
+        .map(|result| result.unwrap().filename)
+        .collect()
+}
+
+#[test]
+fn transient_empty_pages_are_refetched() {
+    let server = flaky_server();
+
+    assert_eq!(filenames(&server, 1), ["a.txt", "b.txt"]);
+    // The final page is also re-fetched before the search ends.
+    assert_eq!(server.requested_pages(), [0, 1, 1, 2, 2]);
+}
+
+#[test]
+fn empty_pages_end_searches_by_default() {
+    let server = flaky_server();
+
+    assert_eq!(filenames(&server, 0), ["a.txt"]);
+    assert_eq!(server.requested_pages(), [0, 1]);
+}
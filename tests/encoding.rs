@@ -0,0 +1,58 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{page, MockServer};
+use sunxdcc::{QueryEncoding, SearchClient};
+
+/// Search for `query`, returning the raw query string of the first request
+/// and the `sterm` parameter as the server decoded it.
+fn sent(query: &str, encoding: QueryEncoding) -> (String, String) {
+    let server = MockServer::pages(vec![page(&["a.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .query_encoding(encoding)
+        .build()
+        .unwrap();
+    client.search(query).next().unwrap().unwrap();
+
+    let request = &server.requests()[0];
+    (request.query.clone(), request.param("sterm").unwrap())
+}
+
+#[test]
+fn encodes_tricky_queries() {
+    let cases = [
+        ("a & b", "sterm=a+%26+b&page=0"),
+        ("a=b", "sterm=a%3Db&page=0"),
+        ("#tag", "sterm=%23tag&page=0"),
+        ("🦀 rust", "sterm=%F0%9F%A6%80+rust&page=0"),
+        ("c++", "sterm=c%2B%2B&page=0"),
+        ("&page=5", "sterm=%26page%3D5&page=0"),
+    ];
+
+    for (query, expected) in cases {
+        let (raw, decoded) = sent(query, QueryEncoding::Plus);
+        assert_eq!(raw, expected, "for {query:?}");
+        assert_eq!(decoded, query);
+    }
+}
+
+#[test]
+fn encodes_spaces_as_configured() {
+    let (raw, decoded) = sent("a & b #tag", QueryEncoding::Percent);
+    assert_eq!(raw, "sterm=a%20%26%20b%20%23tag&page=0");
+    assert_eq!(decoded, "a & b #tag");
+}
+
+#[test]
+fn keeps_existing_base_url_parameters() {
+    let server = MockServer::pages(vec![page(&["a.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(format!("{}?key=secret", server.url()))
+        .build()
+        .unwrap();
+    client.search("#tag").next().unwrap().unwrap();
+
+    assert_eq!(server.requests()[0].query, "key=secret&sterm=%23tag&page=0");
+}
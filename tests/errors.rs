@@ -0,0 +1,42 @@
+#![cfg(feature = "blocking")]
+
+use std::error::Error as _;
+use std::io;
+use std::net::TcpListener;
+
+use sunxdcc::{Error, SearchClient};
+
+#[test]
+fn request_errors_have_sources() {
+    // Nothing listens on a port once its listener is dropped.
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let client = SearchClient::builder()
+        .base_url(format!("http://127.0.0.1:{port}/deliver.php"))
+        .build()
+        .unwrap();
+
+    let err = client.search("foo").next().unwrap().unwrap_err();
+    assert!(matches!(err, Error::Request(_)));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn io_errors_have_sources() {
+    let err = Error::from(io::Error::other("boom"));
+    assert_eq!(err.source().unwrap().to_string(), "boom");
+}
+
+#[test]
+fn other_errors_have_no_source() {
+    assert!(Error::Malformed("bad".into()).source().is_none());
+    assert!(Error::NoResults.source().is_none());
+    assert!(Error::InvalidQuery {
+        reason: "empty".into()
+    }
+    .source()
+    .is_none());
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{page, MockServer};
+use sunxdcc::{Error, SearchClient, SearchResults};
+
+fn server() -> MockServer {
+    MockServer::pages(vec![
+        page(&["Spider-Man.720p.mkv", "Spider-Man.1080p.mkv"]),
+        page(&[
+            "Spider-Man.HDTV.mkv",
+            "Spider-Man.720p.x265.mkv",
+            "Spider-Man.2160p.mkv",
+        ]),
+    ])
+}
+
+fn client(server: &MockServer, parse: bool) -> SearchClient {
+    SearchClient::builder()
+        .base_url(server.url())
+        .parse_excluded_terms(parse)
+        .build()
+        .unwrap()
+}
+
+fn filenames(results: SearchResults) -> Vec<String> {
+    results.map(|result| result.unwrap().filename).collect()
+}
+
+#[test]
+fn hyphenated_words_are_not_excluded() {
+    let server = server();
+    let results = client(&server, true).search("spider-man");
+
+    assert_eq!(results.sent_query(), "spider-man");
+    assert_eq!(filenames(results).len(), 5);
+    assert_eq!(server.requests()[0].param("sterm").unwrap(), "spider-man");
+}
+
+#[test]
+fn trailing_terms_are_excluded() {
+    let server = server();
+    let results = client(&server, true).search("spider-man -720p -HDTV");
+
+    assert_eq!(results.sent_query(), "spider-man");
+    assert_eq!(
+        filenames(results),
+        ["Spider-Man.1080p.mkv", "Spider-Man.2160p.mkv"]
+    );
+    assert_eq!(server.requests()[0].param("sterm").unwrap(), "spider-man");
+}
+
+#[test]
+fn only_trailing_terms_are_excluded() {
+    let server = server();
+    let results = client(&server, true).search("-ology spider-man");
+
+    assert_eq!(results.sent_query(), "-ology spider-man");
+    assert_eq!(filenames(results).len(), 5);
+}
+
+#[test]
+fn terms_are_not_parsed_by_default() {
+    let server = server();
+    let results = client(&server, false).search("spider-man -720p");
+
+    assert_eq!(results.sent_query(), "spider-man -720p");
+    assert_eq!(filenames(results).len(), 5);
+    assert_eq!(
+        server.requests()[0].param("sterm").unwrap(),
+        "spider-man -720p"
+    );
+}
+
+#[test]
+fn query_of_only_excluded_terms_is_invalid() {
+    let server = server();
+    let mut results = client(&server, true).search("-720p");
+
+    assert!(matches!(results.next(), Some(Err(Error::EmptyQuery))));
+    assert_eq!(server.request_count(), 0);
+}
+
+#[test]
+fn resumed_searches_keep_excluded_terms() {
+    let server = server();
+    let mut results = client(&server, true).search("spider-man -720p");
+    assert_eq!(
+        results.next().unwrap().unwrap().filename,
+        "Spider-Man.1080p.mkv"
+    );
+
+    let state = results.state();
+    assert_eq!(state.query, "spider-man");
+    assert_eq!(state.excluded_terms, ["720p"]);
+
+    // The resuming client doesn't parse (or know about) the exclusions.
+    let resumed = SearchResults::from_state(state, &client(&server, false));
+    assert_eq!(
+        filenames(resumed),
+        ["Spider-Man.HDTV.mkv", "Spider-Man.2160p.mkv"]
+    );
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::{PageInfo, SearchClient};
+
+#[test]
+fn on_page_reports_each_page() {
+    let server = MockServer::pages(vec![
+        page(&["a.txt", "b.txt"]),
+        page(&["c.txt"]),
+        page(&["d.txt", "e.txt", "f.txt"]),
+    ]);
+    let pages = Arc::new(Mutex::new(vec![]));
+    let client = {
+        let pages = pages.clone();
+        SearchClient::builder()
+            .base_url(server.url())
+            .on_page(move |info: &PageInfo| pages.lock().unwrap().push(*info))
+            .build()
+            .unwrap()
+    };
+
+    assert_eq!(client.search("foo").count(), 6);
+
+    let pages = pages.lock().unwrap();
+    let summary: Vec<_> = pages
+        .iter()
+        .map(|info| (info.page, info.results, info.total_results))
+        .collect();
+    // The final, empty page ends the search, and is reported too.
+    assert_eq!(summary, [(0, 2, 2), (1, 1, 3), (2, 3, 6), (3, 0, 6)]);
+}
+
+#[test]
+fn on_page_error_reports_failed_pages() {
+    let server = MockServer::start(|request| match request.page() {
+        0 => Response::ok(page(&["a.txt"])),
+        1 => Response::status(500, "internal server error"),
+        _ => Response::ok(empty_page()),
+    });
+    let (pages, errors) = (Arc::new(Mutex::new(vec![])), Arc::new(Mutex::new(vec![])));
+    let client = {
+        let (pages, errors) = (pages.clone(), errors.clone());
+        SearchClient::builder()
+            .base_url(server.url())
+            .on_page(move |info: &PageInfo| pages.lock().unwrap().push(info.page))
+            .on_page_error(move |page, _| errors.lock().unwrap().push(page))
+            .build()
+            .unwrap()
+    };
+
+    let results: Vec<_> = client.search("foo").take(2).collect();
+    assert!(results[0].is_ok() && results[1].is_err());
+    assert_eq!(*pages.lock().unwrap(), [0]);
+    assert_eq!(*errors.lock().unwrap(), [1]);
+}
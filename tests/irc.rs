@@ -0,0 +1,138 @@
+#![cfg(feature = "irc")]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use sunxdcc::irc::{self, IrcRequestError, RequestOptions};
+use sunxdcc::SearchResult;
+
+fn result(network: &str) -> SearchResult {
+    SearchResult {
+        network: network.into(),
+        channel: "#foo".into(),
+        bot: "foobot".into(),
+        filesize: "[1M]".into(),
+        filename: "foo.txt".into(),
+        packet_number: "#12".into(),
+        download_count: "0x".into(),
+        upload_speed: None,
+    }
+}
+
+/// Start a fake IRC server that registers (or, if `reject_nick`, rejects) a
+/// single client, returning its port and a handle yielding every line it
+/// received.
+fn fake_server(reject_nick: bool) -> (u16, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut received = vec![];
+
+        // The client may hang up at any point, e.g. after an error reply.
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            let reply = match line.split(' ').next().unwrap() {
+                "USER" if reject_nick => {
+                    ":irc.foo.net 433 * arthurdent :Nickname is already in use\r\n".to_string()
+                }
+                "USER" => "PING :token\r\n:irc.foo.net 001 arthurdent :Welcome\r\n".into(),
+                "JOIN" => format!(":arthurdent!~a@localhost JOIN {}\r\n", &line[5..]),
+                _ => String::new(),
+            };
+            let _ = writer.write_all(reply.as_bytes());
+            received.push(line);
+        }
+
+        received
+    });
+
+    (port, handle)
+}
+
+#[test]
+fn requests_packs() {
+    let (port, server) = fake_server(false);
+    let options = RequestOptions::new("arthurdent")
+        .port(port)
+        .timeout(Duration::from_secs(5));
+
+    irc::request_pack(&result("127.0.0.1"), &options).unwrap();
+
+    assert_eq!(
+        server.join().unwrap(),
+        [
+            "NICK arthurdent",
+            "USER arthurdent 0 * :arthurdent",
+            "PONG :token",
+            "JOIN #foo",
+            "PRIVMSG foobot :xdcc send #12",
+            "QUIT",
+        ]
+    );
+}
+
+#[test]
+fn reports_rejected_nicknames() {
+    let (port, _server) = fake_server(true);
+    let options = RequestOptions::new("arthurdent")
+        .port(port)
+        .timeout(Duration::from_secs(5));
+
+    let err = irc::request_pack(&result("127.0.0.1"), &options).unwrap_err();
+    assert!(matches!(err, IrcRequestError::Server(text) if text.starts_with("433")));
+}
+
+/// Requests a (nonexistent) pack on a real network.
+#[test]
+#[ignore = "requires network access to irc.libera.chat"]
+fn requests_packs_on_a_live_network() {
+    let nick = format!("sunxdcc{}", std::process::id() % 100_000);
+    let options = RequestOptions::new(nick).timeout(Duration::from_secs(30));
+
+    let mut result = result("irc.libera.chat");
+    result.channel = "#sunxdcc-test".into();
+    irc::request_pack(&result, &options).unwrap();
+}
+
+#[test]
+fn joins_only_the_primary_channel() {
+    let (port, server) = fake_server(false);
+    let options = RequestOptions::new("arthurdent")
+        .port(port)
+        .timeout(Duration::from_secs(5));
+
+    let mut result = result("127.0.0.1");
+    result.channel = "#foo #bar".into();
+    irc::request_pack(&result, &options).unwrap();
+
+    assert_eq!(server.join().unwrap()[3], "JOIN #foo");
+}
+
+#[test]
+fn rejects_injected_commands_before_connecting() {
+    // Nothing listens here, so connecting at all would fail differently.
+    let options = RequestOptions::new("arthurdent")
+        .port(1)
+        .timeout(Duration::from_secs(5));
+
+    let mut bad_bot = result("127.0.0.1");
+    bad_bot.bot = "foobot :hi\r\nPRIVMSG NickServ".into();
+    assert!(matches!(
+        irc::request_pack(&bad_bot, &options),
+        Err(IrcRequestError::InvalidField { field: "bot", .. })
+    ));
+
+    let mut bad_packet = result("127.0.0.1");
+    bad_packet.packet_number = "#12\r\nQUIT".into();
+    assert!(matches!(
+        irc::request_pack(&bad_packet, &options),
+        Err(IrcRequestError::InvalidField {
+            field: "packet number",
+            ..
+        })
+    ));
+}
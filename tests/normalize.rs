@@ -0,0 +1,57 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{page, MockServer};
+use sunxdcc::{SearchBuilder, SearchClient};
+
+/// Search for `query` with the given client configuration, returning the
+/// raw query string of the first request.
+fn sent(query: &str, configure: impl FnOnce(SearchBuilder) -> SearchBuilder) -> String {
+    let server = MockServer::pages(vec![page(&["a.txt"])]);
+    let client = configure(SearchClient::builder().base_url(server.url()))
+        .build()
+        .unwrap();
+
+    let mut results = client.search(query);
+    results.next().unwrap().unwrap();
+    assert_eq!(results.query(), query);
+
+    server.requests()[0].query.clone()
+}
+
+#[test]
+fn normalizes_unicode_queries() {
+    assert_eq!(sent("Amélie", |b| b), "sterm=Am%C3%A9lie&page=0");
+    assert_eq!(
+        sent("  Amélie ", |b| b.normalize_query(true)),
+        "sterm=am%C3%A9lie&page=0"
+    );
+}
+
+#[test]
+fn leaves_ascii_queries_untouched() {
+    for query in ["amelie poulain", "c++ & c#"] {
+        assert_eq!(sent(query, |b| b.normalize_query(true)), sent(query, |b| b));
+    }
+}
+
+#[cfg(feature = "unicode")]
+#[test]
+fn composes_and_strips_diacritics() {
+    // A decomposed `e` + combining acute accent is composed into `é`.
+    assert_eq!(
+        sent("Ame\u{301}lie", |b| b.normalize_query(true)),
+        "sterm=am%C3%A9lie&page=0"
+    );
+    assert_eq!(
+        sent("Amélie", |b| b
+            .normalize_query(true)
+            .strip_diacritics(true)),
+        "sterm=amelie&page=0"
+    );
+    assert_eq!(
+        sent("amelie poulain", |b| b.strip_diacritics(true)),
+        sent("amelie poulain", |b| b)
+    );
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{page, MockServer};
+use sunxdcc::{CacheConfig, SearchClient, SearchResult};
+
+fn search(client: &SearchClient, query: &str) -> Vec<SearchResult> {
+    client.search(query).collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn identical_searches_share_pages() {
+    let server = MockServer::pages(vec![page(&["a.txt", "b.txt"]), page(&["c.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .cache(CacheConfig::new(16, Duration::from_secs(60)))
+        .build()
+        .unwrap();
+
+    let first = search(&client, "foo");
+    assert_eq!(server.request_count(), 3);
+
+    assert_eq!(search(&client, "foo"), first);
+    assert_eq!(server.request_count(), 3);
+
+    let cache = client.cache().unwrap();
+    assert_eq!((cache.hits(), cache.misses()), (3, 3));
+
+    // Other queries are cached separately.
+    search(&client, "bar");
+    assert_eq!(server.request_count(), 6);
+}
+
+#[test]
+fn expired_pages_are_refetched() {
+    let server = MockServer::pages(vec![page(&["a.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .cache(CacheConfig::new(16, Duration::ZERO))
+        .build()
+        .unwrap();
+
+    search(&client, "foo");
+    search(&client, "foo");
+    assert_eq!(server.request_count(), 4);
+}
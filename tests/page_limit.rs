@@ -0,0 +1,75 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::{CollectOptions, Criterion, SearchClient};
+
+/// Start a server with 150 pages of one result each, well past the default
+/// page limit.
+fn server() -> MockServer {
+    MockServer::start(|request| match request.page() {
+        page_number @ 0..150 => Response::ok(page(&[&format!("{page_number}.txt")])),
+        _ => Response::ok(empty_page()),
+    })
+}
+
+fn client(server: &MockServer, max_pages: Option<usize>) -> SearchClient {
+    let builder = SearchClient::builder().base_url(server.url());
+    match max_pages {
+        Some(max_pages) => builder.max_pages(max_pages),
+        None => builder,
+    }
+    .build()
+    .unwrap()
+}
+
+#[test]
+fn iteration_stops_at_the_default_page_limit() {
+    let server = server();
+    let mut results = client(&server, None).search("foo");
+
+    assert_eq!(results.by_ref().count(), 100);
+    assert!(results.was_truncated());
+}
+
+#[test]
+fn whole_search_methods_ignore_the_default_page_limit() {
+    let server = server();
+    let client = client(&server, None);
+
+    let collected = client
+        .search("foo")
+        .collect_all(CollectOptions::new())
+        .unwrap();
+    assert_eq!(collected.len(), 150);
+
+    let top = client.search("foo").top_n(200, Criterion::Size).unwrap();
+    assert_eq!(top.len(), 150);
+
+    // Every result is 1M.
+    assert_eq!(client.search("foo").total_bytes().unwrap(), 150 << 20);
+
+    let best = client.search("foo").max_by_size().unwrap().unwrap();
+    assert_eq!(best.filename, "0.txt");
+}
+
+#[test]
+fn whole_search_methods_respect_explicit_page_limits() {
+    let server = server();
+    let client = client(&server, Some(120));
+
+    assert_eq!(client.search("foo").total_bytes().unwrap(), 120 << 20);
+
+    let collected = client
+        .search("foo")
+        .collect_all(CollectOptions::new().max_pages(130))
+        .unwrap();
+    assert_eq!(collected.len(), 120);
+
+    let collected = client
+        .search("foo")
+        .collect_all(CollectOptions::new().max_pages(110))
+        .unwrap();
+    assert_eq!(collected.len(), 110);
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{page, MockServer};
+use sunxdcc::{SearchClient, SearchResults};
+
+fn server() -> MockServer {
+    MockServer::pages(vec![
+        page(&["a.txt", "b.txt"]),
+        page(&["c.txt"]),
+        page(&["d.txt"]),
+        page(&["e.txt"]),
+    ])
+}
+
+fn search(server: &MockServer) -> SearchResults {
+    SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap()
+        .search("foo")
+}
+
+fn next_filename(results: &mut SearchResults) -> String {
+    results.next().unwrap().unwrap().filename
+}
+
+#[test]
+fn skipping_before_the_first_fetch() {
+    let server = server();
+    let mut results = search(&server);
+
+    results.skip_pages(2);
+    assert_eq!(results.current_page(), 2);
+    assert_eq!(next_filename(&mut results), "d.txt");
+    assert_eq!(server.requests()[0].param("page").unwrap(), "2");
+}
+
+#[test]
+fn skipping_discards_buffered_results() {
+    let server = server();
+    let mut results = search(&server);
+
+    assert_eq!(next_filename(&mut results), "a.txt");
+    assert_eq!(results.current_page(), 1);
+
+    // `b.txt` is discarded, and page 1 is skipped.
+    results.skip_pages(1);
+    assert_eq!(next_filename(&mut results), "d.txt");
+    assert_eq!(server.requested_pages(), [0, 2]);
+}
+
+#[test]
+fn restarting_refetches_the_first_page() {
+    let server = server();
+    let mut results = search(&server);
+
+    results.skip_pages(3);
+    assert_eq!(next_filename(&mut results), "e.txt");
+    results.restart();
+    assert_eq!(results.current_page(), 0);
+    assert_eq!(next_filename(&mut results), "a.txt");
+    assert_eq!(server.requested_pages(), [3, 0]);
+}
+
+#[test]
+fn with_pages_annotates_page_boundaries() {
+    let server = MockServer::pages(vec![page(&["a.txt", "b.txt"]), page(&["c.txt", "d.txt"])]);
+
+    let annotated: Vec<_> = search(&server)
+        .with_pages()
+        .map(|result| {
+            let (page, result) = result.unwrap();
+            (page, result.filename)
+        })
+        .collect();
+    assert_eq!(
+        annotated,
+        [
+            (0, "a.txt".into()),
+            (0, "b.txt".into()),
+            (1, "c.txt".into()),
+            (1, "d.txt".into()),
+        ]
+    );
+}
+
+#[test]
+fn with_pages_reflects_skipped_pages() {
+    let server = server();
+    let mut results = search(&server);
+    results.skip_pages(1);
+
+    let pages: Vec<_> = results
+        .with_pages()
+        .map(|result| result.unwrap().0)
+        .collect();
+    assert_eq!(pages, [1, 2, 3]);
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{page, MockServer};
+use sunxdcc::SearchClient;
+
+const DELAY: Duration = Duration::from_millis(200);
+
+#[test]
+fn every_page_fetch_is_delayed() {
+    let server = MockServer::pages(vec![page(&["a.txt"]), page(&["b.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let results = client.search("foo").with_rate_limit(DELAY);
+    assert_eq!(results.count(), 2);
+
+    // Pages 1 and 2 (which is empty) are each delayed.
+    assert!(start.elapsed() >= 2 * DELAY);
+    assert_eq!(server.requested_pages(), [0, 1, 2]);
+}
+
+#[test]
+fn pages_fetched_for_a_single_result_are_delayed() {
+    // The first two pages are entirely excluded, so the first result needs
+    // three fetches.
+    let server = MockServer::pages(vec![
+        page(&["a.720p.mkv"]),
+        page(&["b.720p.mkv"]),
+        page(&["c.1080p.mkv"]),
+    ]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .exclude_terms(&["720p"])
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let mut results = client.search("foo").with_rate_limit(DELAY);
+    assert_eq!(results.next().unwrap().unwrap().filename, "c.1080p.mkv");
+
+    assert!(start.elapsed() >= 2 * DELAY);
+    assert_eq!(server.requested_pages(), [0, 1, 2]);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{page, MockServer};
+use sunxdcc::{Error, SearchClient, SearchResult, SearchResults};
+
+#[test]
+fn replays_recorded_searches() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = MockServer::pages(vec![page(&["a.txt", "b.txt"]), page(&["c.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let recorded: Vec<SearchResult> = client
+        .search("foo bar")
+        .record_to(dir.path())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(recorded.len(), 3);
+    assert_eq!(server.request_count(), 3);
+    for page in 0..3 {
+        assert!(dir.path().join(format!("page-{page}.rec")).is_file());
+    }
+
+    let replayed: Vec<SearchResult> = SearchResults::replay_from(dir.path(), "foo bar")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(replayed, recorded);
+    assert_eq!(server.request_count(), 3);
+}
+
+#[test]
+fn rejects_recordings_for_other_queries() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = MockServer::pages(vec![page(&["a.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    client.search("foo").record_to(dir.path()).for_each(drop);
+
+    let mut replayed = SearchResults::replay_from(dir.path(), "bar");
+    assert!(matches!(replayed.next(), Some(Err(Error::Malformed(_)))));
+}
+
+#[test]
+fn missing_recordings_are_errors() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut replayed = SearchResults::replay_from(dir.path(), "foo");
+    assert!(matches!(replayed.next(), Some(Err(Error::Io(_)))));
+}
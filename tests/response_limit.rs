@@ -0,0 +1,38 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{page, MockServer, Response};
+use sunxdcc::{Error, SearchClient};
+
+#[test]
+fn streamed_responses_past_the_limit_are_cut_off() {
+    let server = MockServer::start(|_| Response::streamed(1024 * 1024));
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .max_response_bytes(10_000)
+        .build()
+        .unwrap();
+
+    let mut results = client.search("foo");
+    assert!(matches!(
+        results.next(),
+        Some(Err(Error::ResponseTooLarge {
+            limit: 10_000,
+            got_at_least: 10_001
+        }))
+    ));
+}
+
+#[test]
+fn unlimited_responses_are_read_in_full() {
+    let server = MockServer::pages(vec![page(&["foo.txt"])]);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .max_response_bytes(usize::MAX)
+        .build()
+        .unwrap();
+
+    let results = client.search("foo").collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(results.len(), 1);
+}
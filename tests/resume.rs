@@ -0,0 +1,74 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{page, MockServer};
+use sunxdcc::{SearchClient, SearchResults, SearchState};
+
+fn filenames(results: &mut SearchResults, n: usize) -> Vec<String> {
+    results
+        .take(n)
+        .map(|result| result.unwrap().filename)
+        .collect()
+}
+
+/// Take `n` results from a search, then resume it from a checkpoint with a
+/// fresh client, returning the checkpoint, the remaining results, and the
+/// pages requested by the resumed search.
+fn resume_after(n: usize) -> (SearchState, Vec<String>, Vec<usize>) {
+    let pages = vec![
+        page(&["a.txt", "b.txt", "c.txt"]),
+        page(&["d.txt", "e.txt"]),
+    ];
+    let server = MockServer::pages(pages.clone());
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let mut results = client.search("foo");
+    assert_eq!(filenames(&mut results, n).len(), n);
+    let state = results.state();
+
+    let server = MockServer::pages(pages);
+    let client = SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    let mut resumed = SearchResults::from_state(state.clone(), &client);
+    let rest = filenames(&mut resumed, usize::MAX);
+    assert_eq!(resumed.yielded(), 5);
+
+    (state, rest, server.requested_pages())
+}
+
+#[test]
+fn resumes_mid_page() {
+    let (state, rest, requested) = resume_after(2);
+
+    assert_eq!(state.page, 1);
+    assert_eq!(state.yielded, 2);
+    assert_eq!(state.buffered.len(), 1);
+    assert_eq!(rest, ["c.txt", "d.txt", "e.txt"]);
+    assert_eq!(requested, [1, 2]);
+}
+
+#[test]
+fn resumes_at_a_page_boundary() {
+    let (state, rest, requested) = resume_after(3);
+
+    assert_eq!(state.page, 1);
+    assert_eq!(state.yielded, 3);
+    assert!(state.buffered.is_empty());
+    assert_eq!(rest, ["d.txt", "e.txt"]);
+    assert_eq!(requested, [1, 2]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn states_round_trip_through_json() {
+    let (state, _, _) = resume_after(2);
+
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(serde_json::from_str::<SearchState>(&json).unwrap(), state);
+}
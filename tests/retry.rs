@@ -0,0 +1,77 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use common::{empty_page, page, MockServer, Response};
+use sunxdcc::{Error, SearchClient, SearchResults};
+
+/// Start a server that rate-limits its first two requests, first with a
+/// `Retry-After` in seconds and then with one as an HTTP-date (in the past).
+fn rate_limited_server() -> MockServer {
+    let count = AtomicUsize::new(0);
+    MockServer::start(move |request| match count.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::status(429, "slow down").header("Retry-After", "1"),
+        1 => Response::status(429, "slow down")
+            .header("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        _ if request.page() == 0 => Response::ok(page(&["a.txt"])),
+        _ => Response::ok(empty_page()),
+    })
+}
+
+fn search(server: &MockServer) -> SearchResults {
+    SearchClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap()
+        .search("foo")
+}
+
+#[test]
+fn retries_after_the_requested_delay() {
+    let server = rate_limited_server();
+    let start = Instant::now();
+
+    let results: Vec<_> = search(&server)
+        .retry_budget(2)
+        .map(|result| result.unwrap().filename)
+        .collect();
+
+    assert_eq!(results, ["a.txt"]);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(server.requested_pages(), [0, 0, 0, 1]);
+}
+
+#[test]
+fn fails_once_the_budget_is_exhausted() {
+    let server = rate_limited_server();
+    let mut results = search(&server).retry_budget(1);
+
+    // The HTTP-date is in the past, meaning "retry now".
+    assert_eq!(
+        results.next().unwrap().unwrap_err(),
+        Error::RateLimited {
+            retry_after: Some(Duration::ZERO)
+        }
+    );
+    assert_eq!(server.request_count(), 2);
+}
+
+#[test]
+fn reports_future_retry_dates() {
+    let retry_at = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+    let server = MockServer::start(move |_| {
+        Response::status(429, "slow down").header("Retry-After", &retry_at)
+    });
+
+    let Some(Err(Error::RateLimited {
+        retry_after: Some(retry_after),
+    })) = search(&server).next()
+    else {
+        panic!("expected a rate limiting error");
+    };
+    assert!(retry_after > Duration::from_secs(55) && retry_after <= Duration::from_secs(60));
+    assert_eq!(server.request_count(), 1);
+}